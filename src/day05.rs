@@ -1,45 +1,25 @@
-use std::{cmp::Ordering, collections::HashMap};
+use graph::{graph_respects_order, graph_toposort_induced, Directedness, Graph, NodeIndex};
 
 use crate::prelude::*;
 
-fn parse_input(input: &str) -> (HashMap<usize, Vec<usize>>, Vec<Vec<usize>>) {
+fn parse_input(input: &str) -> (Graph<usize>, Vec<Vec<NodeIndex>>) {
     let (orderings, updates) = input.split_once("\n\n").unwrap();
-    let mut followers: HashMap<usize, Vec<usize>> = HashMap::new();
-    for line in orderings.lines() {
-        let (before, after) = line
-            .split("|")
-            .map(|num| num.parse().unwrap())
-            .collect_tuple()
-            .unwrap();
-
-        followers.entry(before).or_default().push(after);
-    }
-
-    let updates = updates.lines().map(|line| {
-        line.split(",")
-            .map(|num| num.parse().unwrap())
-            .collect_vec()
-    });
-
-    (followers, updates.collect())
-}
-
-fn compare(a: &usize, b: &usize, followers: &HashMap<usize, Vec<usize>>) -> Ordering {
-    if followers
-        .get(a)
-        .map(|followers| followers.contains(b))
-        .unwrap()
-    {
-        Ordering::Less
-    } else {
-        // Some sanity checks to make sure we have a total order
-        assert!(a != b);
-        assert!(followers
-            .get(b)
-            .map(|followers| followers.contains(a))
-            .unwrap());
-        Ordering::Greater
-    }
+    let mut followers = Graph::from_edges(
+        Directedness::Directed,
+        graph::parse_edges(orderings, "|")
+            .map(|(before, after)| (before.parse().unwrap(), after.parse().unwrap())),
+    );
+
+    // a page which appears in no rule can go anywhere in its update
+    let updates = updates
+        .lines()
+        .map(|line| {
+            line.split(",")
+                .map(|num| followers.intern(num.parse().unwrap()))
+                .collect_vec()
+        })
+        .collect();
+    (followers, updates)
 }
 
 pub fn part1(input: &str, _is_sample: bool) -> usize {
@@ -47,8 +27,8 @@ pub fn part1(input: &str, _is_sample: bool) -> usize {
 
     updates
         .into_iter()
-        .filter(|update| update.is_sorted_by(|a, b| compare(a, b, &followers) == Ordering::Less))
-        .map(|update| update[update.len() / 2])
+        .filter(|update| graph_respects_order(&followers, update))
+        .map(|update| followers.node(update[update.len() / 2]))
         .sum()
 }
 
@@ -57,11 +37,11 @@ pub fn part2(input: &str, _is_sample: bool) -> usize {
 
     updates
         .into_iter()
-        .filter(|update| !update.is_sorted_by(|a, b| compare(a, b, &followers) == Ordering::Less))
+        .filter(|update| !graph_respects_order(&followers, update))
         .map(|update| {
-            let mut sorted = update.clone();
-            sorted.sort_by(|a, b| compare(a, b, &followers));
-            sorted[sorted.len() / 2]
+            let sorted = graph_toposort_induced(&followers, &update)
+                .expect("Page ordering rules should not have cycles within an update");
+            followers.node(sorted[sorted.len() / 2])
         })
         .sum()
 }
@@ -1,92 +1,17 @@
-use bitvec::vec::BitVec;
+use log::debug;
 
 use crate::prelude::*;
 
-#[repr(isize)]
-#[derive(Clone, Copy)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Direction {
-    pub fn turn_right(&self) -> Self {
-        match self {
-            Self::Up => Self::Right,
-            Self::Right => Self::Down,
-            Self::Down => Self::Left,
-            Self::Left => Self::Up,
-        }
-    }
-
-    pub fn heading(&self) -> (Index, Index) {
-        match self {
-            Self::Up => (0, -1),
-            Self::Right => (1, 0),
-            Self::Down => (0, 1),
-            Self::Left => (-1, 0),
-        }
-    }
-}
-
-struct Visited {
-    width: usize,
-    bitvec: BitVec,
-}
-
-impl Visited {
-    fn new(width: usize, height: usize) -> Self {
-        Self {
-            width: width,
-            bitvec: BitVec::repeat(false, width * height * 4),
-        }
-    }
-
-    fn insert(&mut self, location: Location, heading: Direction) -> bool {
-        let index = (location.y as usize * self.width + location.x as usize) * 4 + heading as usize;
-        let was_set = *self.bitvec.get(index).unwrap();
-        self.bitvec.set(index, true);
-        was_set
-    }
-
-    fn iter(&self) -> impl Iterator<Item = (Location, Direction)> + '_ {
-        self.bitvec
-            .iter()
-            .enumerate()
-            .filter_map(move |(index, set)| {
-                if *set {
-                    let y = index / (self.width * 4);
-                    let x = (index % (self.width * 4)) / 4;
-                    let heading = index % 4;
-                    Some((
-                        Location::new(x as Index, y as Index),
-                        match heading {
-                            0 => Direction::Up,
-                            1 => Direction::Right,
-                            2 => Direction::Down,
-                            3 => Direction::Left,
-                            _ => unreachable!(),
-                        },
-                    ))
-                } else {
-                    None
-                }
-            })
-    }
-}
-
-fn walk(map: &Grid<char>, start: Location) -> Option<Visited> {
-    let mut visited = Visited::new(map.width(), map.height());
+fn walk(map: &Grid<char>, start: Location) -> Option<StateSet<Cardinal>> {
+    let mut visited = StateSet::for_grid(map);
 
     let mut now = map.cell(start).unwrap();
-    let mut direction = Direction::Up;
+    let mut direction = Direction::North;
     loop {
-        if visited.insert(now.location(), direction) {
+        if !visited.insert(now.location(), Cardinal(direction)) {
             break None;
         }
-        if let Some(in_front) = now.offset(direction.heading().0, direction.heading().1) {
+        if let Some(in_front) = now.step(direction.heading()) {
             if *in_front.contents() == '#' {
                 // turn 90 degrees to the right
                 direction = direction.turn_right();
@@ -106,7 +31,13 @@ pub fn part1(input: &str, _is_sample: bool) -> usize {
         .find(|cell| *cell.contents() == '^')
         .unwrap()
         .location();
-    walk(&map, start).unwrap().iter().unique_by(|v| v.0).count()
+    let visited = walk(&map, start).unwrap();
+    debug!(
+        "\n{}",
+        map.clone()
+            .overlay(visited.iter().map(|(location, _)| location), 'X')
+    );
+    visited.locations().count()
 }
 
 pub fn part2(input: &str, _is_sample: bool) -> usize {
@@ -118,11 +49,7 @@ pub fn part2(input: &str, _is_sample: bool) -> usize {
         .location();
 
     let visited = walk(&map, start).unwrap();
-    let possible_obstacles = visited
-        .iter()
-        .unique_by(|v| v.0)
-        .map(|(location, _)| location)
-        .collect_vec();
+    let possible_obstacles = visited.locations().iter().collect_vec();
 
     possible_obstacles
         .into_par_iter()
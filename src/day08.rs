@@ -14,8 +14,7 @@ pub fn part1(input: &str, _is_sample: bool) -> usize {
         .filter_map(|(a, b)| {
             if a != b && a.contents() == b.contents() {
                 let a_to_b = b.location() - a.location();
-                let twice_b = a.location() + a_to_b * 2;
-                map.cell(twice_b)
+                a.look(a_to_b, 2)
             } else {
                 None
             }
@@ -25,7 +24,7 @@ pub fn part1(input: &str, _is_sample: bool) -> usize {
 }
 
 pub fn part2(input: &str, _is_sample: bool) -> usize {
-    let map = &Grid::new_with_lines(input.lines());
+    let map = Grid::new_with_lines(input.lines());
     let stations = map.cells().filter(|c| *c.contents() != '.');
 
     stations
@@ -33,12 +32,9 @@ pub fn part2(input: &str, _is_sample: bool) -> usize {
         .cartesian_product(stations)
         .flat_map(|(a, b)| {
             if a != b && a.contents() == b.contents() {
-                let a_to_b = b.location() - a.location();
-                let mut now = a.location();
-                Either::Left(iter::from_fn(move || {
-                    now += a_to_b;
-                    map.cell(now)
-                }))
+                // every lattice point on the line counts, not just multiples of `a_to_b`
+                let (unit, _) = primitive_heading(b.location() - a.location());
+                Either::Left(ray(a.location(), unit).take_while_in(&map))
             } else {
                 Either::Right(iter::empty())
             }
@@ -1,23 +1,23 @@
-use std::collections::HashMap;
-
 use crate::prelude::*;
 
 pub fn part1(input: &str, _is_sample: bool) -> usize {
     let map = parse_map(input);
     map.cells()
         .filter(is_trail_head)
-        .map(|trail_head: Cell<'_, u32>| graph::find(trail_head, next_steps, is_goal).count())
+        .map(|trail_head| {
+            // distinct summits rather than paths, which memoized path counts can't give
+            graph::find_with_visited(trail_head, next_steps, is_goal, LocationSet::for_grid(&map))
+                .count()
+        })
         .sum()
 }
 
 pub fn part2(input: &str, _is_sample: bool) -> usize {
     let map = parse_map(input);
-
-    let mut paths_from: HashMap<Location, usize> = HashMap::new();
-
+    let mut counter = graph::PathCounter::new(is_goal, |&cell| next_steps(cell));
     map.cells()
         .filter(is_trail_head)
-        .map(|trail_head| get_paths(&map, trail_head, &mut paths_from))
+        .map(|trail_head| counter.count_from(trail_head).unwrap() as usize)
         .sum()
 }
 
@@ -38,26 +38,3 @@ fn is_trail_head(cell: &Cell<'_, u32>) -> bool {
 fn is_goal(cell: &Cell<'_, u32>) -> bool {
     *cell.contents() == 9
 }
-
-fn get_paths(
-    map: &Grid<u32>,
-    cell: Cell<'_, u32>,
-    paths_from: &mut HashMap<Location, usize>,
-) -> usize {
-    if let Some(&paths) = paths_from.get(&cell.location()) {
-        return paths;
-    }
-
-    let result = {
-        if is_goal(&cell) {
-            1
-        } else {
-            next_steps(cell)
-                .map(|n| get_paths(map, n, paths_from))
-                .sum()
-        }
-    };
-
-    paths_from.insert(cell.location(), result);
-    result
-}
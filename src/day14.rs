@@ -37,12 +37,8 @@ pub fn part2(input: &str, is_sample: bool) -> usize {
             let num_well_connected = grid
                 .cells()
                 .filter(|cell| {
-                    *cell.contents() == '#'
-                        && cell
-                            .cardinal_neighbors()
-                            .filter(|c| *c.contents() == '#')
-                            .count()
-                            >= 2
+                    *cell.contents()
+                        && cell.cardinal_neighbors().filter(|c| *c.contents()).count() >= 2
                 })
                 .count();
             num_well_connected > robots.len() / 2 // "most of the robots"
@@ -51,7 +47,10 @@ pub fn part2(input: &str, is_sample: bool) -> usize {
         .1;
 
     let final_grid = build_grid(width, height, robots.iter(), result as i64);
-    debug!("{}", final_grid);
+    debug!(
+        "{}",
+        final_grid.render(|c| if *c.contents() { '#' } else { '.' })
+    );
 
     result
 }
@@ -74,15 +73,10 @@ fn build_grid<'a>(
     height: usize,
     robots: impl Iterator<Item = &'a (i64, i64, i64, i64)>,
     i: i64,
-) -> Grid<char> {
-    let mut grid = Grid::new('.', width, height);
-    for pos in robots.map(|(x, y, vx, vy)| {
-        (
-            (x + vx * i).rem_euclid(width as i64),
-            (y + vy * i).rem_euclid(height as i64),
-        )
-    }) {
-        grid.set(point2(pos.0 as Index, pos.1 as Index), '#');
+) -> Grid<bool, Toroidal> {
+    let mut grid = Grid::new(false, width, height).with_topology::<Toroidal>();
+    for (x, y, vx, vy) in robots {
+        grid.set(point2((x + vx * i) as Index, (y + vy * i) as Index), true);
     }
     grid
 }
@@ -1,61 +1,110 @@
 use std::collections::HashSet;
 
-use log::debug;
+use log::{debug, log_enabled, Level};
 
 use crate::prelude::*;
 
-fn heading(c: char) -> Option<Vector2D<Index>> {
-    match c {
-        '^' => Some(vec2(0, -1)),
-        'v' => Some(vec2(0, 1)),
-        '<' => Some(vec2(-1, 0)),
-        '>' => Some(vec2(1, 0)),
-        _ => None,
-    }
-}
-
 pub fn part1(input: &str, _is_sample: bool) -> usize {
     let (map, directions) = parse(input);
     solve(map, directions)
 }
 
 fn solve(mut map: Grid<char>, directions: impl Iterator<Item = Vector2D<isize>>) -> usize {
-    let mut robot = map
+    let robot = map
         .cells()
         .find(|c| *c.contents() == '@')
         .unwrap()
         .location();
-
     map.set(robot, '.');
 
+    // recording every move is only worth it when the steps are being logged
+    let map = if log_enabled!(Level::Debug) {
+        let mut recording = Recording::new(map);
+        run(&mut recording, robot, directions);
+        recording.into_grid()
+    } else {
+        run(&mut map, robot, directions);
+        map
+    };
+
+    map.cells()
+        .filter(|c| *c.contents() == 'O' || *c.contents() == '[')
+        .map(|c| c.location().x + c.location().y * 100)
+        .sum::<isize>() as usize
+}
+
+/// A warehouse map the robot can move boxes around in.
+trait Warehouse {
+    fn grid(&self) -> &Grid<char>;
+
+    fn set(&mut self, location: Location, c: char);
+
+    /// Called once each move is complete.
+    fn end_step(&mut self, _step: usize, _robot: Location) {}
+}
+
+impl Warehouse for Grid<char> {
+    fn grid(&self) -> &Grid<char> {
+        self
+    }
+
+    fn set(&mut self, location: Location, c: char) {
+        Grid::set(self, location, c)
+    }
+}
+
+impl Warehouse for Recording<char> {
+    fn grid(&self) -> &Grid<char> {
+        Recording::grid(self)
+    }
+
+    fn set(&mut self, location: Location, c: char) {
+        Recording::set(self, location, c)
+    }
+
+    fn end_step(&mut self, step: usize, robot: Location) {
+        match self.changed_region(step) {
+            Some((bounds, region)) => {
+                debug!("Step {}: {:?}, {:?}\n{}", step, robot, bounds, region)
+            }
+            None => debug!("Step {}: {:?}", step, robot),
+        }
+        self.next_step();
+    }
+}
+
+fn run(
+    map: &mut impl Warehouse,
+    mut robot: Location,
+    directions: impl Iterator<Item = Vector2D<isize>>,
+) {
     for (i, d) in directions.enumerate() {
         let next = robot + d;
-        let next_content = *map.cell(next).unwrap().contents();
+        let next_content = map.grid()[next];
         robot = match next_content {
             '.' => next,
-            'O' | '[' | ']' if try_shift_box(&mut map, next, d) => next,
+            'O' | '[' | ']' if try_shift_box(map, next, d) => next,
             _ => robot,
         };
 
-        debug!("Step {}: {:?}\n{}\n\n", i, robot, map);
-        map.cells().for_each(|c| match c.contents() {
-            '[' => debug_assert!(*map.cell(c.location() + vec2(1, 0)).unwrap().contents() == ']'),
-            ']' => debug_assert!(*map.cell(c.location() + vec2(-1, 0)).unwrap().contents() == '['),
+        let grid = map.grid();
+        grid.cells().for_each(|c| match c.contents() {
+            '[' => debug_assert!(grid[c.location() + vec2(1, 0)] == ']'),
+            ']' => debug_assert!(grid[c.location() + vec2(-1, 0)] == '['),
             _ => {}
         });
+        map.end_step(i, robot);
     }
-
-    map.cells()
-        .filter(|c| *c.contents() == 'O' || *c.contents() == '[')
-        .map(|c| c.location().x + c.location().y * 100)
-        .sum::<isize>() as usize
 }
 
 fn parse(input: &str) -> (Grid<char>, impl Iterator<Item = Vector2D<Index>> + use<'_>) {
     let (map, directions) = input.split_once("\n\n").unwrap();
 
     let map = Grid::new_with_lines(map.lines());
-    let directions = directions.chars().flat_map(heading);
+    let directions = directions
+        .chars()
+        .flat_map(Direction::from_char)
+        .map(Direction::heading);
     (map, directions)
 }
 
@@ -111,8 +160,12 @@ fn can_shift_box(
     Some(results.into_iter().flatten().chain(our_moves).collect())
 }
 
-fn try_shift_box(map: &mut Grid<char>, start: Point2D<Index>, direction: Vector2D<Index>) -> bool {
-    if let Some(starts) = can_shift_box(map, start, direction) {
+fn try_shift_box(
+    map: &mut impl Warehouse,
+    start: Point2D<Index>,
+    direction: Vector2D<Index>,
+) -> bool {
+    if let Some(starts) = can_shift_box(map.grid(), start, direction) {
         let mut already_set = HashSet::new();
         for (loc, c) in starts {
             if !already_set.contains(&loc) {
@@ -1,5 +1,3 @@
-use graph::min_distances;
-
 use crate::prelude::*;
 
 struct Puzzle {
@@ -35,14 +33,7 @@ impl Puzzle {
             grid.set(*coord, true);
         }
 
-        min_distances(grid.cell(point2(0, 0)).unwrap(), |cell| {
-            cell.cardinal_neighbors()
-                .filter(|n| !n.contents())
-                .map(|c| (c, 1))
-                .collect()
-        })
-        .get(&grid.cell(self.end()).unwrap())
-        .copied()
+        grid.bfs_distances([point2(0, 0)], |&corrupted| !corrupted)[self.end()].map(u64::from)
     }
 }
 
@@ -1,4 +1,4 @@
-use graph::min_distances;
+use graph::min_distances_zero_one;
 
 use crate::prelude::*;
 
@@ -9,32 +9,29 @@ struct Problem<'a> {
 
 impl<'a> Problem<'a> {
     fn satisfiable(&self) -> bool {
-        min_distances(Vec::new(), |so_far| {
+        min_distances_zero_one(Vec::new(), |so_far| {
             self.pieces
                 .iter()
                 .filter(|piece| self.usable(&so_far, piece))
                 .map(|piece| (so_far.iter().chain(piece.iter()).copied().collect(), 0))
-                .collect()
+                .collect_vec()
         })
         .contains_key(&self.target)
     }
 
     fn count_ways(&self) -> usize {
-        let mut ways = vec![0; self.target.len() + 1];
-        ways[0] = 1;
-        for target_len in 0..=self.target.len() {
-            for piece in self.pieces {
-                let lookback = piece.len();
-                if lookback > target_len {
-                    continue;
-                }
-
-                if self.usable(&self.target[..(target_len - lookback)], piece) {
-                    ways[target_len] += ways[target_len - lookback];
-                }
-            }
-        }
-        *ways.last().unwrap()
+        // nodes are how much of the target has been matched
+        graph::count_paths(
+            0,
+            |&matched| matched == self.target.len(),
+            |&matched| {
+                self.pieces
+                    .iter()
+                    .filter(move |piece| self.target[matched..].starts_with(piece))
+                    .map(move |piece| matched + piece.len())
+            },
+        )
+        .unwrap() as usize
     }
 
     fn usable(&self, target_so_far: &[char], piece: &[char]) -> bool {
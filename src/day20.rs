@@ -1,43 +1,29 @@
-use graph::min_distances;
 use log::debug;
 
 use crate::prelude::*;
 
-fn edges<'a>(c: &Cell<'a, char>) -> Vec<(Cell<'a, char>, u64)> {
-    c.cardinal_neighbors()
-        .filter(|n| *n.contents() != '#')
-        .map(|n| (n, 1))
-        .collect()
-}
-
 pub fn part1(input: &str, _is_sample: bool) -> usize {
     solve(input, 2)
 }
 
 fn solve(input: &str, cheat_distance: usize) -> usize {
     let map = Grid::new_with_lines(input.lines());
-    let start = map.cells().find(|c| *c.contents() == 'S').unwrap();
-    let end = map.cells().find(|c| *c.contents() == 'E').unwrap();
+    let start = map.position(|&c| c == 'S').unwrap();
+    let end = map.position(|&c| c == 'E').unwrap();
 
-    let distances_from_start = &min_distances(start, edges);
-    let distances_to_end = &min_distances(end, edges);
+    let distances_from_start = &map.bfs_distances([start], |&c| c != '#');
+    let distances_to_end = &map.bfs_distances([end], |&c| c != '#');
 
-    let no_cheating_cost = *distances_from_start
-        .get(&end)
-        .expect("no path from start to end");
+    let no_cheating_cost = distances_from_start[end].expect("no path from start to end");
 
-    let possible_cheats = distances_from_start
-        .into_iter()
+    let possible_cheats = map
+        .locations()
+        .filter_map(|cheat_start| Some((cheat_start, distances_from_start[cheat_start]?)))
         .flat_map(|(cheat_start, cost_to_cheat_start)| {
-            distances_to_end
-                .iter()
-                .filter_map(move |(cheat_end, cost_to_cheat_end)| {
-                    let needed_cheat_distance = cheat_start.manhattan_distance(cheat_end);
-                    if needed_cheat_distance > cheat_distance {
-                        return None;
-                    } else {
-                        Some(cost_to_cheat_start + cost_to_cheat_end + needed_cheat_distance as u64)
-                    }
+            map.distances_within(cheat_start, cheat_distance)
+                .filter_map(move |(cheat_end, needed_cheat_distance)| {
+                    let cost_to_cheat_end = distances_to_end[cheat_end]?;
+                    Some(cost_to_cheat_start + cost_to_cheat_end + needed_cheat_distance as u32)
                 })
         })
         .sorted()
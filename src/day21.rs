@@ -58,19 +58,11 @@ impl Keypad {
         let mut now = from;
         let mut path = Vec::new();
         for heading in headings {
-            now = now.offset(heading.x, heading.y).unwrap();
+            now = now.step(heading).unwrap();
             if *now.contents() == '#' {
                 return None;
             }
-            path.push(if heading == NORTH {
-                '^'
-            } else if heading == SOUTH {
-                'v'
-            } else if heading == EAST {
-                '>'
-            } else {
-                '<'
-            });
+            path.push(Direction::try_from(heading).unwrap().arrow());
         }
 
         Some(path)
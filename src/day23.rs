@@ -1,80 +1,26 @@
-use std::iter;
+use graph::{cliques_of_size, max_clique, parse_edges, Directedness, Graph};
 
 use crate::prelude::*;
 
 pub fn part1(input: &str, _is_sample: bool) -> usize {
-    let nodes = &parse(input);
+    let graph = parse(input);
 
-    nodes
+    cliques_of_size(&graph, 3)
         .iter()
-        .filter(|(n, _)| n.starts_with("t"))
-        .flat_map(|(node, connected)| {
-            connected
-                .iter()
-                .cartesian_product(connected.iter())
-                .filter_map(move |(a, b)| {
-                    if nodes[a].contains(b) {
-                        Some([node, a, b].into_iter().sorted().collect_vec())
-                    } else {
-                        None
-                    }
-                })
-        })
-        .unique()
+        .filter(|triangle| triangle.iter().any(|&n| graph.node(n).starts_with("t")))
         .count()
 }
 
-fn parse(input: &str) -> HashMap<&str, Vec<&str>> {
-    let mut nodes: HashMap<&str, Vec<&str>> = HashMap::new();
-    let pairs = input
-        .lines()
-        .map(|line| line.split("-").collect_tuple().unwrap());
-    for (a, b) in pairs {
-        nodes.entry(a).or_default().push(b);
-        nodes.entry(b).or_default().push(a);
-    }
-    for v in nodes.values_mut() {
-        v.sort();
-    }
-    nodes
-}
-
-fn largest_component_with<'n>(
-    nodes: &HashMap<&'n str, Vec<&'n str>>,
-    so_far: Vec<&'n str>,
-    candidates: &[&'n str],
-) -> Vec<&'n str> {
-    // This could be faster by merge-sort-like reduction of candidates as we add each but this is ok for our size
-    candidates
-        .iter()
-        .enumerate()
-        .filter_map(|(i, candidate)| {
-            if so_far
-                .iter()
-                .all(|current| candidate > current && nodes[current].contains(candidate))
-            {
-                let next = so_far
-                    .iter()
-                    .chain(iter::once(candidate))
-                    .copied()
-                    .collect_vec();
-                Some(largest_component_with(nodes, next, &candidates[i + 1..]))
-            } else {
-                None
-            }
-        })
-        .max_by_key(|c| c.len())
-        .unwrap_or(so_far)
+fn parse(input: &str) -> Graph<&str> {
+    Graph::from_edges(Directedness::Undirected, parse_edges(input, "-"))
 }
 
 pub fn part2(input: &str, _is_sample: bool) -> String {
-    let nodes = &parse(input);
-    let mut largest = nodes
-        .keys()
-        .map(|n| largest_component_with(nodes, vec![n], &nodes[n]))
-        .max_by_key(|n| n.len())
-        .unwrap();
+    let graph = parse(input);
 
-    largest.sort();
-    largest.iter().join(",")
+    max_clique(&graph)
+        .iter()
+        .map(|&n| graph.node(n))
+        .sorted()
+        .join(",")
 }
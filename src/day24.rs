@@ -3,6 +3,8 @@ use rand::prelude::*;
 use rand_chacha::ChaCha8Rng;
 use std::{collections::HashSet, fmt::Display, str::FromStr};
 
+use graph::{toposort, Cycle};
+
 use crate::prelude::*;
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
@@ -71,11 +73,28 @@ fn eval<'a>(
     value
 }
 
+/// Every wire, ordered so that each gate's output comes after the wires it reads.
+fn evaluation_order<'a>(
+    expressions: &HashMap<&'a str, Expr<'a>>,
+) -> Result<Vec<&'a str>, Cycle<&'a str>> {
+    // edges go from each gate's output to its inputs, so this is the reverse order
+    let mut order = toposort(expressions.keys().copied(), |output| {
+        expressions
+            .get(output)
+            .into_iter()
+            .flat_map(|expr| [expr.a, expr.b])
+    })?;
+    order.reverse();
+    Ok(order)
+}
+
 pub fn part1(input: &str, _is_sample: bool) -> usize {
     let (mut values, expressions) = parse(input);
 
-    for var in variable_msb_first("z", &values, &expressions) {
-        eval(var, &mut values, &expressions);
+    for var in evaluation_order(&expressions).expect("Gates should not form a cycle") {
+        if let Some(expr) = expressions.get(var) {
+            values.insert(var, expr.op.eval(values[expr.a], values[expr.b]));
+        }
     }
 
     let result = to_usize("z", &values, &expressions);
@@ -174,6 +193,13 @@ pub fn part2(input: &str, _is_sample: bool) -> String {
 
         expressions.insert(found, substitute_expr);
         expressions.insert(substitute, first_wrong_expr);
+        // `eval` would recurse forever on a cycle
+        if let Err(cycle) = evaluation_order(&expressions) {
+            panic!(
+                "Swapping {found} and {substitute} made a cycle: {:?}",
+                cycle.nodes
+            );
+        }
 
         swaps.push(found);
         swaps.push(substitute);
@@ -1,30 +1,82 @@
 use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashMap, HashSet},
+    cmp::Ordering,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque},
+    fmt::Debug,
     hash::Hash,
-    iter,
+    iter, mem,
+    ops::Sub,
+    slice,
 };
 
 use itertools::Itertools;
+use log::warn;
+use num_traits::{Bounded, CheckedAdd, One, Zero};
+use rayon::prelude::*;
+
+use crate::grid::{chebyshev_distance, manhattan_distance, Location};
+
+mod adjacency;
+mod cliques;
+mod counting;
+mod ordering;
+mod strongly_connected;
+pub use adjacency::*;
+pub use cliques::*;
+pub use counting::*;
+pub use ordering::*;
+pub use strongly_connected::*;
+
+/// The set of nodes a search has already visited. This lets searches over grids use dense
+/// storage like `grid::LocationSet` instead of hashing every node.
+pub trait VisitedSet<V> {
+    /// Returns true if the node was not already in the set.
+    fn insert(&mut self, node: V) -> bool;
+
+    fn contains(&self, node: &V) -> bool;
+}
+
+impl<V> VisitedSet<V> for HashSet<V>
+where
+    V: Eq + Hash,
+{
+    fn insert(&mut self, node: V) -> bool {
+        HashSet::insert(self, node)
+    }
+
+    fn contains(&self, node: &V) -> bool {
+        HashSet::contains(self, node)
+    }
+}
 
 pub fn find<V, EdgeIterator>(
+    start: V,
+    edges: impl FnMut(V) -> EdgeIterator,
+    predicate: impl FnMut(&V) -> bool,
+) -> impl Iterator<Item = V>
+where
+    EdgeIterator: Iterator<Item = V>,
+    V: Eq + Hash + Clone,
+{
+    find_with_visited(start, edges, predicate, HashSet::new())
+}
+
+pub fn find_with_visited<V, EdgeIterator>(
     start: V,
     mut edges: impl FnMut(V) -> EdgeIterator,
     mut predicate: impl FnMut(&V) -> bool,
+    mut visited: impl VisitedSet<V>,
 ) -> impl Iterator<Item = V>
 where
     EdgeIterator: Iterator<Item = V>,
-    V: Eq + Hash + Clone,
+    V: Clone,
 {
-    let mut visited = HashSet::new();
     let mut queue = vec![start];
 
     std::iter::from_fn(move || {
         while let Some(node) = queue.pop() {
-            if visited.contains(&node) {
+            if !visited.insert(node.clone()) {
                 continue;
             }
-            visited.insert(node.clone());
             queue.extend(edges(node.clone()));
 
             if predicate(&node) {
@@ -35,49 +87,199 @@ where
     })
 }
 
-pub fn min_distances<V>(start: V, edges: impl Fn(&V) -> Vec<(V, u64)>) -> HashMap<V, u64>
+/// Edge weights and path distances. Sums are checked, so that a distance which overflows
+/// is reported rather than wrapping around. `Bounded::max_value()` is larger than any distance,
+/// for marking unreachable nodes. Float weights must not be NaN; searches panic if one is queued.
+pub trait Weight: Copy + PartialOrd + Zero + Bounded + Debug {
+    /// The sum, or None if it overflows.
+    fn checked_plus(self, other: Self) -> Option<Self>;
+
+    /// The sum, panicking if it overflows.
+    fn plus(self, other: Self) -> Self {
+        self.checked_plus(other)
+            .unwrap_or_else(|| panic!("Distance overflowed adding {:?} to {:?}", other, self))
+    }
+}
+
+macro_rules! integer_weights {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn checked_plus(self, other: Self) -> Option<Self> {
+                    CheckedAdd::checked_add(&self, &other)
+                }
+            }
+        )*
+    };
+}
+
+integer_weights!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+macro_rules! float_weights {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn checked_plus(self, other: Self) -> Option<Self> {
+                    let sum = self + other;
+                    sum.is_finite().then_some(sum)
+                }
+            }
+        )*
+    };
+}
+
+float_weights!(f32, f64);
+
+/// An entry in a Dijkstra queue. Ordered only by distance (smallest first in a `BinaryHeap`)
+/// so that nodes don't need to be `Ord`.
+struct QueueEntry<V, W> {
+    distance: W,
+    node: V,
+}
+
+impl<V, W: Weight> PartialEq for QueueEntry<V, W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl<V, W: Weight> Eq for QueueEntry<V, W> {}
+
+impl<V, W: Weight> PartialOrd for QueueEntry<V, W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V, W: Weight> Ord for QueueEntry<V, W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .expect("Distances should not be NaN")
+    }
+}
+
+pub fn min_distances<V, W, EI>(start: V, edges: impl FnMut(&V) -> EI) -> HashMap<V, W>
 where
-    V: Eq + Ord + Hash + Clone,
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
 {
     min_distances_inner(start, edges, |_, _| false)
 }
 
-fn min_distances_inner<V>(
+/// Dijkstra's algorithm. Only relaxations which improve on a node's best known distance are
+/// queued, and queue entries made stale by a later improvement are skipped before expanding,
+/// so each node is expanded once. If stopped early, the result includes the best known
+/// distances to nodes which were not yet settled.
+fn min_distances_inner<V, W, EI>(
     start: V,
-    edges: impl Fn(&V) -> Vec<(V, u64)>,
-    mut should_stop: impl FnMut(&V, u64) -> bool,
-) -> HashMap<V, u64>
+    mut edges: impl FnMut(&V) -> EI,
+    mut should_stop: impl FnMut(&V, W) -> bool,
+) -> HashMap<V, W>
 where
-    V: Eq + Ord + Hash + Clone,
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
 {
-    let mut distances = HashMap::new();
+    let mut distances = HashMap::from([(start.clone(), W::zero())]);
     let mut queue = BinaryHeap::new();
-    queue.push(Reverse((0, start)));
-    while let Some(Reverse((distance, node))) = queue.pop() {
-        if let Some(&previous_distance) = distances.get(&node) {
-            if distance >= previous_distance {
-                continue;
-            }
+    queue.push(QueueEntry {
+        distance: W::zero(),
+        node: start,
+    });
+    while let Some(QueueEntry { distance, node }) = queue.pop() {
+        if distances[&node] < distance {
+            continue;
         }
-        distances.insert(node.clone(), distance);
         if should_stop(&node, distance) {
             break;
         }
-        for (next_node, next_distance) in edges(&node) {
-            queue.push(Reverse((distance + next_distance, next_node)));
+        for (next_node, edge_distance) in edges(&node) {
+            debug_assert!(
+                edge_distance >= W::zero(),
+                "Edge weights should be non-negative"
+            );
+            let next_distance = distance.plus(edge_distance);
+            if let Some(next_node) = improve(&mut distances, next_node, next_distance) {
+                queue.push(QueueEntry {
+                    distance: next_distance,
+                    node: next_node,
+                });
+            }
         }
     }
 
     distances
 }
 
-pub fn min_distance_to<V>(
+/// Records the distance if it's better than the best known one, returning the node if so.
+fn improve<V, W>(distances: &mut HashMap<V, W>, node: V, distance: W) -> Option<V>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+{
+    match distances.entry(node) {
+        Entry::Occupied(entry) if *entry.get() <= distance => None,
+        Entry::Occupied(mut entry) => {
+            entry.insert(distance);
+            Some(entry.key().clone())
+        }
+        Entry::Vacant(entry) => {
+            let node = entry.key().clone();
+            entry.insert(distance);
+            Some(node)
+        }
+    }
+}
+
+/// Like `min_distances`, for graphs whose edges all have weight 0 or 1: a breadth-first
+/// search which puts weight 0 edges at the front of the queue instead of using a heap.
+pub fn min_distances_zero_one<V, W, EI>(start: V, mut edges: impl FnMut(&V) -> EI) -> HashMap<V, W>
+where
+    V: Eq + Hash + Clone,
+    W: Weight + One,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    let mut distances = HashMap::from([(start.clone(), W::zero())]);
+    let mut queue = VecDeque::from([(start, W::zero())]);
+    while let Some((node, distance)) = queue.pop_front() {
+        if distances[&node] < distance {
+            continue;
+        }
+        for (next_node, edge_distance) in edges(&node) {
+            assert!(
+                edge_distance.is_zero() || edge_distance.is_one(),
+                "Edge weight should be 0 or 1"
+            );
+            debug_assert!(
+                edge_distance >= W::zero(),
+                "Edge weights should be non-negative"
+            );
+            let next_distance = distance.plus(edge_distance);
+            if let Some(next_node) = improve(&mut distances, next_node, next_distance) {
+                if edge_distance.is_zero() {
+                    queue.push_front((next_node, next_distance));
+                } else {
+                    queue.push_back((next_node, next_distance));
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+pub fn min_distance_to<V, W, EI>(
     start: V,
-    goal: impl Fn(&V, u64) -> bool,
-    edges: impl Fn(&V) -> Vec<(V, u64)>,
-) -> Option<u64>
+    goal: impl Fn(&V, W) -> bool,
+    edges: impl FnMut(&V) -> EI,
+) -> Option<W>
 where
-    V: Eq + Ord + Hash + Clone,
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
 {
     let mut found_goal = None;
     min_distances_inner(start, edges, |node, distance| {
@@ -91,100 +293,664 @@ where
     found_goal
 }
 
-pub struct DistanceStorage<V> {
-    indexes: HashMap<V, usize>,
-    distances: Vec<Option<u64>>,
+/// The shortest distances from a start node, and for each node reached, every predecessor
+/// it has along some shortest path. Together these form a DAG of all the optimal paths
+/// (as long as there are no zero-weight cycles).
+#[derive(Debug, Clone)]
+pub struct PredecessorMap<V, W> {
+    /// Where each node is in `nodes`. The start is always first.
+    indices: HashMap<V, usize>,
+    nodes: Vec<(V, Reached<W>)>,
+}
+
+/// What the search knows about a node: its best distance so far, whether that is final, and
+/// the indices of its predecessors at that distance.
+#[derive(Debug, Clone)]
+struct Reached<W> {
+    distance: W,
+    settled: bool,
+    predecessors: Predecessors,
+}
+
+/// Most nodes have one or two predecessors, so those are stored without allocating.
+#[derive(Debug, Clone)]
+enum Predecessors {
+    One(usize),
+    Two([usize; 2]),
+    Many(Vec<usize>),
+}
+
+impl Predecessors {
+    fn as_slice(&self) -> &[usize] {
+        match self {
+            Predecessors::One(index) => slice::from_ref(index),
+            Predecessors::Two(indices) => indices,
+            Predecessors::Many(indices) => indices,
+        }
+    }
+
+    /// Adds a predecessor, unless it's already there through a parallel edge.
+    fn push(&mut self, index: usize) {
+        if self.as_slice().contains(&index) {
+            return;
+        }
+        match self {
+            Predecessors::One(first) => *self = Predecessors::Two([*first, index]),
+            Predecessors::Two([first, second]) => {
+                *self = Predecessors::Many(vec![*first, *second, index])
+            }
+            Predecessors::Many(indices) => indices.push(index),
+        }
+    }
 }
 
-impl<V> DistanceStorage<V>
+impl<V, W> PredecessorMap<V, W>
 where
     V: Eq + Hash + Clone,
+    W: Weight,
 {
-    fn new(values: impl Iterator<Item = V>) -> Self {
-        let indexes: HashMap<V, usize> = values.enumerate().map(|(i, v)| (v, i)).collect();
-        let distances = vec![None; indexes.len() * indexes.len()];
-        Self { indexes, distances }
+    pub fn start(&self) -> &V {
+        &self.nodes[0].0
     }
 
-    fn index(&self, start: &V, end: &V) -> usize {
-        let start_index = self.indexes[start];
-        let end_index = self.indexes[end];
-        start_index * self.length() + end_index
+    fn settled_index(&self, node: &V) -> Option<usize> {
+        let &index = self.indices.get(node)?;
+        self.nodes[index].1.settled.then_some(index)
     }
 
-    pub fn get(&self, start: &V, end: &V) -> Option<u64> {
-        self.distances[self.index(start, end)]
+    fn predecessor_indices(&self, index: usize) -> &[usize] {
+        self.nodes[index].1.predecessors.as_slice()
     }
 
-    fn set(&mut self, start: &V, end: &V, distance: u64) -> Option<u64> {
-        let index = self.index(start, end);
-        let previous = self.distances[index];
-        self.distances[index] = Some(distance);
-        previous
+    pub fn distance(&self, node: &V) -> Option<W> {
+        Some(self.nodes[self.settled_index(node)?].1.distance)
     }
 
-    fn vertex_indices(&self) -> impl Iterator<Item = usize> {
-        0..self.indexes.len()
+    /// Every node reached, with its shortest distance.
+    pub fn distances(&self) -> impl Iterator<Item = (&V, W)> + '_ {
+        self.nodes
+            .iter()
+            .filter(|(_, reached)| reached.settled)
+            .map(|(node, reached)| (node, reached.distance))
+    }
+
+    /// The nodes just before this one on its shortest paths; empty for the start and for
+    /// unreached nodes.
+    pub fn predecessors(&self, node: &V) -> impl Iterator<Item = &V> + '_ {
+        let indices = match self.settled_index(node) {
+            Some(index) => self.predecessor_indices(index),
+            None => &[],
+        };
+        indices.iter().map(|&index| &self.nodes[index].0)
+    }
+
+    /// One shortest path from the start to the goal, including both ends.
+    pub fn path_to(&self, goal: &V) -> Option<Vec<V>> {
+        self.paths_to(goal).next()
+    }
+
+    /// Every shortest path from the start to the goal, including both ends.
+    pub fn paths_to(&self, goal: &V) -> impl Iterator<Item = Vec<V>> + '_ {
+        // from the goal back toward the start, with the position of the next predecessor to try
+        let mut stack = self
+            .settled_index(goal)
+            .map(|index| (index, 0))
+            .into_iter()
+            .collect_vec();
+
+        iter::from_fn(move || {
+            while let Some((index, next_position)) = stack.last_mut() {
+                if *index == 0 {
+                    let path = stack
+                        .iter()
+                        .rev()
+                        .map(|&(index, _)| self.nodes[index].0.clone())
+                        .collect_vec();
+                    stack.pop();
+                    return Some(path);
+                }
+                match self.predecessor_indices(*index).get(*next_position) {
+                    Some(&predecessor) => {
+                        *next_position += 1;
+                        stack.push((predecessor, 0));
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Every node on any shortest path from the start to any of the goals.
+    pub fn nodes_on_paths_to(&self, goals: impl IntoIterator<Item = V>) -> HashSet<V> {
+        let mut on_paths = vec![false; self.nodes.len()];
+        let mut queue = goals
+            .into_iter()
+            .filter_map(|goal| self.settled_index(&goal))
+            .collect_vec();
+        while let Some(index) = queue.pop() {
+            if !mem::replace(&mut on_paths[index], true) {
+                queue.extend_from_slice(self.predecessor_indices(index));
+            }
+        }
+        on_paths
+            .into_iter()
+            .zip(&self.nodes)
+            .filter(|&(on_path, _)| on_path)
+            .map(|(_, (node, _))| node.clone())
+            .collect()
+    }
+}
+
+/// Like `min_distances`, but also records every node's predecessors on its shortest paths.
+pub fn all_shortest_path_dag<V, W, EI>(
+    start: V,
+    edges: impl FnMut(&V) -> EI,
+) -> PredecessorMap<V, W>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    shortest_path_dag_inner(start, edges, |_| false)
+}
+
+/// A shortest path from the start to the first goal node reached, with its length.
+pub fn shortest_path<V, W, EI>(
+    start: V,
+    goal: impl Fn(&V) -> bool,
+    edges: impl FnMut(&V) -> EI,
+) -> Option<(Vec<V>, W)>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    let mut found_goal = None;
+    let dag = shortest_path_dag_inner(start, edges, |node| {
+        if goal(node) {
+            found_goal = Some(node.clone());
+            true
+        } else {
+            false
+        }
+    });
+    let goal = found_goal?;
+    Some((dag.path_to(&goal)?, dag.distance(&goal)?))
+}
+
+fn shortest_path_dag_inner<V, W, EI>(
+    start: V,
+    mut edges: impl FnMut(&V) -> EI,
+    mut should_stop: impl FnMut(&V) -> bool,
+) -> PredecessorMap<V, W>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    // the queue and predecessors refer to nodes by their index, so they are only cloned once
+    let mut indices = HashMap::from([(start.clone(), 0)]);
+    let mut nodes = vec![(
+        start,
+        Reached {
+            distance: W::zero(),
+            settled: false,
+            predecessors: Predecessors::Many(vec![]),
+        },
+    )];
+    let mut queue = BinaryHeap::new();
+    queue.push(QueueEntry {
+        distance: W::zero(),
+        node: 0,
+    });
+
+    while let Some(QueueEntry {
+        distance,
+        node: index,
+    }) = queue.pop()
+    {
+        let reached = &mut nodes[index].1;
+        if reached.settled || reached.distance < distance {
+            continue;
+        }
+        reached.settled = true;
+        if should_stop(&nodes[index].0) {
+            break;
+        }
+        for (next_node, edge_distance) in edges(&nodes[index].0) {
+            debug_assert!(
+                edge_distance >= W::zero(),
+                "Edge weights should be non-negative"
+            );
+            let next_distance = distance.plus(edge_distance);
+            match indices.entry(next_node) {
+                Entry::Occupied(entry) => {
+                    let next_index = *entry.get();
+                    let next = &mut nodes[next_index].1;
+                    // a settled node can only be reached again at the same distance, through
+                    // a zero-weight edge
+                    if next_distance < next.distance {
+                        next.distance = next_distance;
+                        next.predecessors = Predecessors::One(index);
+                        queue.push(QueueEntry {
+                            distance: next_distance,
+                            node: next_index,
+                        });
+                    } else if next_distance == next.distance {
+                        next.predecessors.push(index);
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    let next_index = nodes.len();
+                    nodes.push((
+                        entry.key().clone(),
+                        Reached {
+                            distance: next_distance,
+                            settled: false,
+                            predecessors: Predecessors::One(index),
+                        },
+                    ));
+                    entry.insert(next_index);
+                    queue.push(QueueEntry {
+                        distance: next_distance,
+                        node: next_index,
+                    });
+                }
+            }
+        }
+    }
+
+    PredecessorMap { indices, nodes }
+}
+
+/// The distance from the start to the first goal node reached, searching toward the nodes
+/// with the smallest `distance + heuristic(node)` first. The heuristic must never overestimate
+/// the distance to a goal; in debug builds, a warning is logged if it isn't consistent.
+pub fn astar<V, W, EI>(
+    start: V,
+    goal: impl Fn(&V) -> bool,
+    mut edges: impl FnMut(&V) -> EI,
+    heuristic: impl Fn(&V) -> W,
+) -> Option<W>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    let mut warned = false;
+    let mut best_distances = HashMap::from([(start.clone(), W::zero())]);
+    let mut queue = BinaryHeap::new();
+    queue.push(QueueEntry {
+        distance: heuristic(&start),
+        node: (start, W::zero()),
+    });
+
+    while let Some(QueueEntry {
+        node: (node, distance),
+        ..
+    }) = queue.pop()
+    {
+        if best_distances[&node] < distance {
+            continue;
+        }
+        let estimate = heuristic(&node);
+        if goal(&node) {
+            if cfg!(debug_assertions) && !estimate.is_zero() {
+                warn!("A* heuristic is {:?} at a goal, rather than 0", estimate);
+            }
+            return Some(distance);
+        }
+        for (next_node, edge_distance) in edges(&node) {
+            debug_assert!(
+                edge_distance >= W::zero(),
+                "Edge weights should be non-negative"
+            );
+            let next_distance = distance.plus(edge_distance);
+            let next_estimate = heuristic(&next_node);
+            if cfg!(debug_assertions) && !warned && estimate > edge_distance.plus(next_estimate) {
+                warn!(
+                    "A* heuristic is inconsistent: {:?} across an edge of {:?} to {:?}",
+                    estimate, edge_distance, next_estimate
+                );
+                warned = true;
+            }
+            if best_distances
+                .get(&next_node)
+                .is_some_and(|&best| best <= next_distance)
+            {
+                continue;
+            }
+            best_distances.insert(next_node.clone(), next_distance);
+            queue.push(QueueEntry {
+                distance: next_distance.plus(next_estimate),
+                node: (next_node, next_distance),
+            });
+        }
+    }
+
+    None
+}
+
+/// An A* heuristic for grids with only cardinal moves of cost 1.
+pub fn manhattan_heuristic(goal: Location) -> impl Fn(&Location) -> u64 {
+    move |location| manhattan_distance(*location, goal) as u64
+}
+
+/// An A* heuristic for grids with diagonal moves, all of cost 1.
+pub fn chebyshev_heuristic(goal: Location) -> impl Fn(&Location) -> u64 {
+    move |location| chebyshev_distance(*location, goal) as u64
+}
+
+/// Shortest distances between every pair of a fixed set of vertices, stored as a flat matrix
+/// with `Bounded::max_value()` for unreachable pairs, along with the next hop on a shortest path
+/// for each pair so that paths can be reconstructed.
+#[derive(Debug, Clone)]
+pub struct DistanceStorage<V, W> {
+    vertices: Vec<V>,
+    indexes: HashMap<V, usize>,
+    distances: Vec<W>,
+    // the index of the vertex after the start on a shortest path, or NO_HOP
+    next_hops: Vec<usize>,
+}
+
+const NO_HOP: usize = usize::MAX;
+
+impl<V, W> DistanceStorage<V, W>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+{
+    /// Starts with the direct edges between the vertices. If there are several edges between
+    /// two vertices, the shortest is used.
+    fn new<EI>(vertices: Vec<V>, mut edges: impl FnMut(&V) -> EI) -> Self
+    where
+        EI: IntoIterator<Item = (V, W)>,
+    {
+        let indexes: HashMap<V, usize> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v.clone(), i))
+            .collect();
+        let length = vertices.len();
+        let mut storage = Self {
+            vertices,
+            indexes,
+            distances: vec![W::max_value(); length * length],
+            next_hops: vec![NO_HOP; length * length],
+        };
+
+        for start in 0..length {
+            storage.improve(start, start, W::zero(), start);
+            for (next, distance) in edges(&storage.vertices[start]) {
+                let end = storage.indexes[&next];
+                storage.improve(start, end, distance, end);
+            }
+        }
+        storage
+    }
+
+    fn improve(&mut self, start: usize, end: usize, distance: W, next_hop: usize) {
+        let index = start * self.length() + end;
+        if distance < self.distances[index] {
+            self.distances[index] = distance;
+            self.next_hops[index] = next_hop;
+        }
     }
 
     fn length(&self) -> usize {
-        self.indexes.len()
+        self.vertices.len()
     }
 
-    fn get_by_index(&self, start_index: usize, end_index: usize) -> Option<u64> {
-        self.distances[start_index * self.length() + end_index]
+    pub fn vertices(&self) -> &[V] {
+        &self.vertices
     }
 
-    fn set_by_index(&mut self, start_index: usize, end_index: usize, distance: u64) -> Option<u64> {
-        let index = start_index * self.length() + end_index;
-        let previous = self.distances[index];
-        self.distances[index] = Some(distance);
-        previous
+    pub fn get(&self, start: &V, end: &V) -> Option<W> {
+        self.get_by_index(self.indexes[start], self.indexes[end])
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&V, &V, u64)> {
-        self.indexes.iter().flat_map(move |(start, start_index)| {
-            self.indexes.iter().map(move |(end, end_index)| {
-                let distance = self.get_by_index(*start_index, *end_index).unwrap();
-                (start, end, distance)
+    fn get_by_index(&self, start_index: usize, end_index: usize) -> Option<W> {
+        let distance = self.distances[start_index * self.length() + end_index];
+        (distance != W::max_value()).then_some(distance)
+    }
+
+    /// A shortest path from the start to the end, including both.
+    pub fn path(&self, start: &V, end: &V) -> Option<Vec<V>> {
+        let (mut now, end) = (self.indexes[start], self.indexes[end]);
+        let mut path = vec![self.vertices[now].clone()];
+        while now != end {
+            now = self.next_hops[now * self.length() + end];
+            if now == NO_HOP {
+                return None;
+            }
+            path.push(self.vertices[now].clone());
+        }
+        Some(path)
+    }
+
+    /// Every pair of vertices with a path between them, with its distance.
+    pub fn iter(&self) -> impl Iterator<Item = (&V, &V, W)> {
+        self.vertices
+            .iter()
+            .enumerate()
+            .flat_map(move |(start_index, start)| {
+                self.vertices
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(end_index, end)| {
+                        let distance = self.get_by_index(start_index, end_index)?;
+                        Some((start, end, distance))
+                    })
             })
-        })
     }
 }
 
-pub fn all_pairs_min_distances<V>(
+/// Floyd-Warshall: the shortest distances between every pair of vertices, which must include
+/// every vertex the edges lead to. Negative edges are allowed, but not negative cycles.
+pub fn all_pairs_min_distances<V, W, EI>(
     verticies: Vec<V>,
-    edges: impl Fn(&V) -> Vec<(V, u64)>,
-) -> DistanceStorage<V>
+    edges: impl FnMut(&V) -> EI,
+) -> DistanceStorage<V, W>
 where
-    V: Eq + Ord + Hash + Clone,
+    V: Eq + Hash + Clone,
+    W: Weight + Send + Sync,
+    EI: IntoIterator<Item = (V, W)>,
 {
-    let mut storage = DistanceStorage::new(verticies.iter().cloned());
-    for start in verticies.iter() {
-        let nexts = edges(start);
-        for (next, distance) in nexts {
-            let previous = storage.set(start, &next, distance);
-            assert!(previous.is_none());
+    let mut storage = DistanceStorage::new(verticies, edges);
+    let length = storage.length();
+    if length == 0 {
+        return storage;
+    }
+
+    for middle in 0..length {
+        // the middle row can't improve in this round, since its distance to itself is 0
+        let middle_distances = storage.distances[middle * length..(middle + 1) * length].to_vec();
+        storage
+            .distances
+            .par_chunks_mut(length)
+            .zip(storage.next_hops.par_chunks_mut(length))
+            .for_each(|(distances, next_hops)| {
+                let start_to_middle = distances[middle];
+                if start_to_middle == W::max_value() {
+                    return;
+                }
+                for (end, &middle_to_end) in middle_distances.iter().enumerate() {
+                    if middle_to_end == W::max_value() {
+                        continue;
+                    }
+                    let total_distance = start_to_middle.plus(middle_to_end);
+                    if total_distance < distances[end] {
+                        distances[end] = total_distance;
+                        next_hops[end] = next_hops[middle];
+                    }
+                }
+            });
+    }
+
+    storage
+}
+
+/// A cycle of edges whose weights sum to less than zero, so that there are no shortest paths
+/// through it. The first node follows the last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegativeCycle<V> {
+    pub nodes: Vec<V>,
+}
+
+/// The shortest distances from the start to every reachable node, allowing negative edge
+/// weights (Bellman-Ford). Fails if a negative cycle is reachable from the start.
+pub fn bellman_ford<V, W, EI>(
+    start: V,
+    mut edges: impl FnMut(&V) -> EI,
+) -> Result<HashMap<V, W>, NegativeCycle<V>>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    // every reachable node, with its edges
+    let mut adjacency = vec![];
+    let mut reached = HashSet::from([start.clone()]);
+    let mut queue = vec![start.clone()];
+    while let Some(node) = queue.pop() {
+        let node_edges = edges(&node).into_iter().collect_vec();
+        for (next_node, _) in &node_edges {
+            if reached.insert(next_node.clone()) {
+                queue.push(next_node.clone());
+            }
         }
+        adjacency.push((node, node_edges));
     }
 
-    for middle in storage.vertex_indices() {
-        for start in storage.vertex_indices() {
-            for end in storage.vertex_indices() {
-                let Some(start_to_middle) = storage.get_by_index(start, middle) else {
-                    continue;
-                };
-                let Some(middle_to_end) = storage.get_by_index(middle, end) else {
+    let mut distances = HashMap::from([(start, W::zero())]);
+    let mut predecessors = HashMap::new();
+    // without negative cycles, every shortest path has fewer edges than there are nodes, so
+    // the distances stop improving before the last round
+    for round in 0..adjacency.len() {
+        let mut last_improved = None;
+        for (node, node_edges) in &adjacency {
+            let Some(&distance) = distances.get(node) else {
+                continue;
+            };
+            for (next_node, edge_distance) in node_edges {
+                let next_distance = distance.plus(*edge_distance);
+                if distances
+                    .get(next_node)
+                    .is_some_and(|&best| best <= next_distance)
+                {
                     continue;
-                };
-                let total_distance = start_to_middle + middle_to_end;
-                let current_distance = storage.get_by_index(start, end).unwrap_or(u64::MAX);
-                storage.set_by_index(start, end, total_distance.min(current_distance));
+                }
+                distances.insert(next_node.clone(), next_distance);
+                predecessors.insert(next_node.clone(), node.clone());
+                last_improved = Some(next_node.clone());
             }
         }
+
+        let Some(mut node) = last_improved else {
+            return Ok(distances);
+        };
+        if round + 1 < adjacency.len() {
+            continue;
+        }
+        // following predecessors from a node still improving must eventually loop
+        let mut seen = HashSet::new();
+        while seen.insert(node.clone()) {
+            node = predecessors[&node].clone();
+        }
+        let mut cycle = vec![node.clone()];
+        let mut now = predecessors[&node].clone();
+        while now != node {
+            cycle.push(now.clone());
+            now = predecessors[&now].clone();
+        }
+        cycle.reverse();
+        return Err(NegativeCycle { nodes: cycle });
     }
 
-    storage
+    Ok(distances)
+}
+
+/// Like `all_pairs_min_distances`, but using Johnson's algorithm: Bellman-Ford to reweight
+/// the edges so that none are negative, and then Dijkstra from each vertex in parallel. This
+/// is faster for sparse graphs. Fails if there is a negative cycle.
+pub fn johnson_min_distances<V, W, EI>(
+    verticies: Vec<V>,
+    mut edges: impl FnMut(&V) -> EI,
+) -> Result<DistanceStorage<V, W>, NegativeCycle<V>>
+where
+    V: Eq + Hash + Clone + Sync,
+    W: Weight + Sub<Output = W> + Send + Sync,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    let mut storage = DistanceStorage::new(verticies, |_| []);
+    let adjacency = storage
+        .vertices
+        .iter()
+        .map(|vertex| {
+            edges(vertex)
+                .into_iter()
+                .map(|(next, distance)| (storage.indexes[&next], distance))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    // distances from a new vertex (None) with an edge of 0 to every vertex
+    let potentials = bellman_ford(None, |&vertex: &Option<usize>| match vertex {
+        None => (0..adjacency.len())
+            .map(|i| (Some(i), W::zero()))
+            .collect_vec(),
+        Some(i) => adjacency[i]
+            .iter()
+            .map(|&(j, d)| (Some(j), d))
+            .collect_vec(),
+    })
+    .map_err(|cycle| NegativeCycle {
+        nodes: cycle
+            .nodes
+            .into_iter()
+            .map(|vertex| storage.vertices[vertex.unwrap()].clone())
+            .collect(),
+    })?;
+    let potential = |i: usize| potentials[&Some(i)];
+    let reweighted = adjacency
+        .iter()
+        .enumerate()
+        .map(|(i, edges)| {
+            edges
+                .iter()
+                .map(|&(j, d)| (j, d.plus(potential(i)) - potential(j)))
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let length = storage.length();
+    let rows = (0..length)
+        .into_par_iter()
+        .map(|start| {
+            let dag = all_shortest_path_dag(start, |&i| reweighted[i].iter().copied());
+            let mut distances = vec![W::max_value(); length];
+            let mut next_hops = vec![NO_HOP; length];
+            for (&end, distance) in dag.distances() {
+                distances[end] = distance.plus(potential(end)) - potential(start);
+                let mut hop = end;
+                while let Some(&previous) = dag.predecessors(&hop).next() {
+                    if previous == start {
+                        break;
+                    }
+                    hop = previous;
+                }
+                next_hops[end] = hop;
+            }
+            (distances, next_hops)
+        })
+        .collect::<Vec<_>>();
+
+    storage.distances = rows.iter().flat_map(|(d, _)| d.iter().copied()).collect();
+    storage.next_hops = rows.iter().flat_map(|(_, n)| n.iter().copied()).collect();
+    Ok(storage)
 }
 
 pub fn all_paths<V>(start: V, end: V, edges: impl Fn(&V) -> Vec<V>) -> impl Iterator<Item = Vec<V>>
@@ -252,3 +1018,250 @@ where
 
     regions
 }
+
+/// Disjoint sets over the indices `0..len`, with path compression and union by size.
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    /// The representative of the set containing `index`.
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut now = index;
+        while self.parents[now] != root {
+            let next = self.parents[now];
+            self.parents[now] = root;
+            now = next;
+        }
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`. Returns false if they were already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (small, large) = if self.sizes[a] < self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+        true
+    }
+
+    /// The size of the set containing `index`.
+    pub fn size(&mut self, index: usize) -> usize {
+        let root = self.find(index);
+        self.sizes[root]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grid::{Connectivity, Grid};
+
+    // a diamond a -> {b, c} -> d with equal costs, plus a longer direct edge a -> d
+    fn diamond(node: &char) -> Vec<(char, u64)> {
+        match node {
+            'a' => vec![('b', 1), ('c', 2), ('d', 5)],
+            'b' => vec![('d', 3)],
+            'c' => vec![('d', 2)],
+            'd' => vec![('e', 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        let dag = all_shortest_path_dag('a', diamond);
+        assert_eq!(
+            dag.distances()
+                .map(|(&node, distance)| (node, distance))
+                .collect::<HashMap<_, _>>(),
+            min_distances('a', diamond)
+        );
+        assert_eq!(dag.predecessors(&'d').collect_vec(), vec![&'b', &'c']);
+        assert_eq!(
+            dag.paths_to(&'e').sorted().collect_vec(),
+            vec![vec!['a', 'b', 'd', 'e'], vec!['a', 'c', 'd', 'e']]
+        );
+        assert_eq!(dag.nodes_on_paths_to(['d']).len(), 4);
+        assert_eq!(dag.paths_to(&'z').count(), 0);
+
+        let (path, distance) = shortest_path('a', |&n| n == 'e', diamond).unwrap();
+        assert_eq!(distance, 5);
+        assert_eq!(
+            (path.first(), path.last(), path.len()),
+            (Some(&'a'), Some(&'e'), 4)
+        );
+        assert_eq!(shortest_path('b', |&n| n == 'a', diamond), None);
+    }
+
+    #[test]
+    fn test_parallel_edges() {
+        // two equal-weight edges from a to b shouldn't double the paths through b
+        let parallel = |node: &char| match node {
+            'a' => vec![('b', 1), ('b', 1), ('c', 1)],
+            'b' | 'c' => vec![('d', 1)],
+            _ => vec![],
+        };
+        let dag = all_shortest_path_dag('a', parallel);
+        assert_eq!(dag.predecessors(&'b').collect_vec(), vec![&'a']);
+        assert_eq!(dag.paths_to(&'d').count(), 2);
+        assert_eq!(
+            count_shortest_paths('a', |&n| n == 'd', parallel),
+            Ok(Some((2u64, 2)))
+        );
+    }
+
+    #[test]
+    fn test_bellman_ford() {
+        let edges = |node: &char| match node {
+            'a' => vec![('b', 4), ('c', 2)],
+            'b' => vec![('d', -3)],
+            'c' => vec![('b', 1), ('d', 4)],
+            _ => vec![],
+        };
+        let distances = bellman_ford('a', edges).unwrap();
+        assert_eq!(distances[&'d'], 0);
+        assert_eq!(distances[&'b'], 3);
+
+        let cyclic = |node: &char| match node {
+            'a' => vec![('b', 1)],
+            'b' => vec![('c', -2)],
+            'c' => vec![('b', 1), ('d', 1)],
+            _ => vec![],
+        };
+        let cycle = bellman_ford('a', cyclic).unwrap_err();
+        assert_eq!(cycle.nodes.len(), 2);
+        assert!(cycle.nodes.contains(&'b') && cycle.nodes.contains(&'c'));
+
+        let floats = |&node: &u8| match node {
+            0 => vec![(1, 0.5), (2, 2.0)],
+            1 => vec![(2, 0.25)],
+            _ => vec![],
+        };
+        assert_eq!(min_distances(0, floats)[&2], 0.75);
+        assert_eq!(bellman_ford(0, floats).unwrap(), min_distances(0, floats));
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        // includes a duplicate edge, a negative edge, and an unreachable vertex
+        let edges = |node: &char| match node {
+            'a' => vec![('b', 7), ('b', 3), ('c', 8)],
+            'b' => vec![('c', 4), ('d', 9)],
+            'c' => vec![('d', -2)],
+            'd' => vec![('a', 1)],
+            _ => vec![],
+        };
+        let vertices = vec!['a', 'b', 'c', 'd', 'e'];
+        let floyd = all_pairs_min_distances(vertices.clone(), edges);
+        let johnson = johnson_min_distances(vertices.clone(), edges).unwrap();
+        for start in &vertices {
+            let expected = bellman_ford(*start, edges).unwrap();
+            for end in &vertices {
+                let distance = expected.get(end).copied();
+                assert_eq!(floyd.get(start, end), distance);
+                assert_eq!(johnson.get(start, end), distance);
+                for storage in [&floyd, &johnson] {
+                    let Some(path) = storage.path(start, end) else {
+                        assert_eq!(distance, None);
+                        continue;
+                    };
+                    let length: i32 = path
+                        .iter()
+                        .tuple_windows()
+                        .map(|(a, b)| {
+                            edges(a)
+                                .iter()
+                                .filter(|(n, _)| n == b)
+                                .map(|&(_, d)| d)
+                                .min()
+                                .unwrap()
+                        })
+                        .sum();
+                    assert_eq!(Some(length), distance);
+                }
+            }
+        }
+        assert_eq!(floyd.path(&'a', &'d'), Some(vec!['a', 'b', 'c', 'd']));
+        assert_eq!(floyd.iter().count(), 17);
+
+        let cyclic = |node: &char| match node {
+            'a' => vec![('b', -1)],
+            _ => vec![('a', -1)],
+        };
+        assert!(johnson_min_distances(vec!['a', 'b'], cyclic).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Distance overflowed")]
+    fn test_weight_overflow() {
+        min_distances(0, |&node: &u8| [(node.wrapping_add(1), 100u8)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-negative")]
+    fn test_negative_weight() {
+        min_distances(0, |&node: &i32| [(node + 1, -1)]);
+    }
+
+    #[test]
+    fn test_min_distances_zero_one() {
+        // steps right are free, steps down cost 1
+        let edges = |&(x, y): &(u8, u8)| {
+            [((x + 1, y), 0), ((x, y + 1), 1), ((x + 1, y + 1), 1)]
+                .into_iter()
+                .filter(|&((x, y), _)| x < 5 && y < 4)
+        };
+        let distances = min_distances_zero_one((0, 0), edges);
+        assert_eq!(distances, min_distances((0, 0), edges));
+        assert_eq!(distances[&(4, 3)], 3);
+        assert_eq!(distances.len(), 20);
+    }
+
+    #[test]
+    fn test_astar() {
+        let grid = Grid::new_with_lines(
+            ["..#.....", "..#.##..", "....#..#", "###.#.#.", "....#..."].iter(),
+        );
+        let start = Location::new(0, 0);
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let edges = |&l: &Location| {
+                grid.neighbors(l, connectivity)
+                    .filter(|&n| grid[n] == '.')
+                    .map(|n| (n, 1))
+                    .collect_vec()
+            };
+            let distances = min_distances(start, edges);
+            for goal in grid.locations().filter(|&l| grid[l] == '.') {
+                let heuristic: Box<dyn Fn(&Location) -> u64> = match connectivity {
+                    Connectivity::Four => Box::new(manhattan_heuristic(goal)),
+                    Connectivity::Eight => Box::new(chebyshev_heuristic(goal)),
+                };
+                assert_eq!(
+                    astar(start, |&l| l == goal, edges, heuristic),
+                    distances.get(&goal).copied(),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::{collections::HashMap, hash::Hash, slice};
+
+use super::Weight;
+
+/// Something that can list the successors of a node. The closure-based searches in this module
+/// take it through `successors_fn`, e.g. `graph::flood_fill_from(starts, graph.successors_fn())`.
+pub trait Adjacency<V> {
+    type Successors<'a>: Iterator<Item = V>
+    where
+        Self: 'a;
+
+    fn successors(&self, node: &V) -> Self::Successors<'_>;
+
+    /// An `edges` closure for the searches in this module.
+    fn successors_fn<'a>(&'a self) -> impl Fn(&V) -> Self::Successors<'a> + 'a {
+        move |node| self.successors(node)
+    }
+}
+
+/// Like `Adjacency`, with a weight for each edge.
+pub trait WeightedAdjacency<V, W: Weight> {
+    type Edges<'a>: Iterator<Item = (V, W)>
+    where
+        Self: 'a;
+
+    fn weighted_edges(&self, node: &V) -> Self::Edges<'_>;
+
+    /// A weighted `edges` closure for the searches in this module.
+    fn weighted_edges_fn<'a>(&'a self) -> impl Fn(&V) -> Self::Edges<'a> + 'a {
+        move |node| self.weighted_edges(node)
+    }
+}
+
+impl<V> Adjacency<V> for HashMap<V, Vec<V>>
+where
+    V: Eq + Hash + Clone,
+{
+    type Successors<'a>
+        = std::iter::Cloned<slice::Iter<'a, V>>
+    where
+        V: 'a;
+
+    fn successors(&self, node: &V) -> Self::Successors<'_> {
+        self.get(node)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directedness {
+    Directed,
+    /// Every edge can be followed both ways.
+    Undirected,
+}
+
+/// Node indices in a `Graph`, which are dense: `0..graph.len()`.
+pub type NodeIndex = usize;
+
+/// A graph with explicit adjacency lists. Nodes are interned, so searches can run over their
+/// `NodeIndex`es rather than hashing the nodes themselves.
+#[derive(Debug, Clone)]
+pub struct Graph<N, E = ()> {
+    directedness: Directedness,
+    nodes: Vec<N>,
+    indexes: HashMap<N, NodeIndex>,
+    edges: Vec<(NodeIndex, NodeIndex, E)>,
+    // (other end, edge index) for each node
+    outgoing: Vec<Vec<(NodeIndex, usize)>>,
+    // only used for directed graphs
+    incoming: Vec<Vec<(NodeIndex, usize)>>,
+}
+
+impl<N, E> Graph<N, E>
+where
+    N: Eq + Hash + Clone,
+{
+    pub fn new(directedness: Directedness) -> Self {
+        Self {
+            directedness,
+            nodes: vec![],
+            indexes: HashMap::new(),
+            edges: vec![],
+            outgoing: vec![],
+            incoming: vec![],
+        }
+    }
+
+    pub fn directed() -> Self {
+        Self::new(Directedness::Directed)
+    }
+
+    pub fn undirected() -> Self {
+        Self::new(Directedness::Undirected)
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directedness == Directedness::Directed
+    }
+
+    /// The index of the node, adding it to the graph if needed.
+    pub fn intern(&mut self, node: N) -> NodeIndex {
+        if let Some(&index) = self.indexes.get(&node) {
+            return index;
+        }
+        let index = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.indexes.insert(node, index);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
+        index
+    }
+
+    pub fn index(&self, node: &N) -> Option<NodeIndex> {
+        self.indexes.get(node).copied()
+    }
+
+    pub fn node(&self, index: NodeIndex) -> &N {
+        &self.nodes[index]
+    }
+
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+
+    pub fn node_indices(&self) -> std::ops::Range<NodeIndex> {
+        0..self.nodes.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds an edge, interning both ends. Returns the index of the edge.
+    pub fn add_edge(&mut self, from: N, to: N, edge: E) -> usize {
+        let (from, to) = (self.intern(from), self.intern(to));
+        let edge_index = self.edges.len();
+        self.edges.push((from, to, edge));
+        self.outgoing[from].push((to, edge_index));
+        match self.directedness {
+            Directedness::Directed => self.incoming[to].push((from, edge_index)),
+            Directedness::Undirected if from != to => self.outgoing[to].push((from, edge_index)),
+            Directedness::Undirected => {}
+        }
+        edge_index
+    }
+
+    /// Every edge as (from, to, edge), in the order they were added. Undirected edges are
+    /// only listed once.
+    pub fn edges(&self) -> impl Iterator<Item = (NodeIndex, NodeIndex, &E)> {
+        self.edges.iter().map(|(from, to, edge)| (*from, *to, edge))
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The edges which can be followed from the node, as (other end, edge).
+    pub fn out_edges(&self, index: NodeIndex) -> impl Iterator<Item = (NodeIndex, &E)> {
+        self.outgoing[index]
+            .iter()
+            .map(|&(other, edge_index)| (other, &self.edges[edge_index].2))
+    }
+
+    /// The nodes with an edge to this one; for undirected graphs, the same as its successors.
+    pub fn predecessors(&self, index: NodeIndex) -> Successors<'_> {
+        let list = match self.directedness {
+            Directedness::Directed => &self.incoming[index],
+            Directedness::Undirected => &self.outgoing[index],
+        };
+        Successors { inner: list.iter() }
+    }
+
+    pub fn contains_edge(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        self.outgoing[from].iter().any(|&(other, _)| other == to)
+    }
+
+    pub fn out_degree(&self, index: NodeIndex) -> usize {
+        self.outgoing[index].len()
+    }
+
+    pub fn in_degree(&self, index: NodeIndex) -> usize {
+        self.predecessors(index).len()
+    }
+
+    /// The number of edges touching the node; for directed graphs, in and out combined.
+    pub fn degree(&self, index: NodeIndex) -> usize {
+        match self.directedness {
+            Directedness::Directed => self.out_degree(index) + self.in_degree(index),
+            Directedness::Undirected => self.out_degree(index),
+        }
+    }
+}
+
+impl<N> Graph<N>
+where
+    N: Eq + Hash + Clone,
+{
+    pub fn from_edges(directedness: Directedness, edges: impl IntoIterator<Item = (N, N)>) -> Self {
+        let mut graph = Self::new(directedness);
+        for (from, to) in edges {
+            graph.add_edge(from, to, ());
+        }
+        graph
+    }
+}
+
+/// Parses lines like `a-b` into pairs of names, for `Graph::from_edges`.
+pub fn parse_edges<'a>(
+    input: &'a str,
+    separator: &'a str,
+) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+    input.lines().map(move |line| {
+        line.split_once(separator)
+            .unwrap_or_else(|| panic!("Edge {:?} should contain {:?}", line, separator))
+    })
+}
+
+/// The other ends of a node's edges in a `Graph`.
+#[derive(Debug, Clone)]
+pub struct Successors<'g> {
+    inner: slice::Iter<'g, (NodeIndex, usize)>,
+}
+
+impl Iterator for Successors<'_> {
+    type Item = NodeIndex;
+
+    fn next(&mut self) -> Option<NodeIndex> {
+        self.inner.next().map(|&(other, _)| other)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Successors<'_> {}
+
+/// The other ends of a node's edges in a `Graph`, with their weights.
+#[derive(Debug, Clone)]
+pub struct WeightedEdges<'g, W> {
+    edges: &'g [(NodeIndex, NodeIndex, W)],
+    inner: slice::Iter<'g, (NodeIndex, usize)>,
+}
+
+impl<W: Copy> Iterator for WeightedEdges<'_, W> {
+    type Item = (NodeIndex, W);
+
+    fn next(&mut self) -> Option<(NodeIndex, W)> {
+        self.inner
+            .next()
+            .map(|&(other, edge_index)| (other, self.edges[edge_index].2))
+    }
+}
+
+impl<N, E> Adjacency<NodeIndex> for Graph<N, E>
+where
+    N: Eq + Hash + Clone,
+{
+    type Successors<'a>
+        = Successors<'a>
+    where
+        Self: 'a;
+
+    fn successors(&self, node: &NodeIndex) -> Successors<'_> {
+        Successors {
+            inner: self.outgoing[*node].iter(),
+        }
+    }
+}
+
+impl<N, W> WeightedAdjacency<NodeIndex, W> for Graph<N, W>
+where
+    N: Eq + Hash + Clone,
+    W: Weight,
+{
+    type Edges<'a>
+        = WeightedEdges<'a, W>
+    where
+        Self: 'a;
+
+    fn weighted_edges(&self, node: &NodeIndex) -> WeightedEdges<'_, W> {
+        WeightedEdges {
+            edges: &self.edges,
+            inner: self.outgoing[*node].iter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{find, flood_fill_from, min_distances};
+
+    #[test]
+    fn test_graph() {
+        let undirected = Graph::from_edges(
+            Directedness::Undirected,
+            parse_edges("a-b\nb-c\nc-a\nc-d", "-"),
+        );
+        let c = undirected.index(&"c").unwrap();
+        assert_eq!(undirected.len(), 4);
+        assert_eq!(undirected.edge_count(), 4);
+        assert_eq!(undirected.degree(c), 3);
+        assert!(undirected.contains_edge(undirected.index(&"d").unwrap(), c));
+        let regions = flood_fill_from([0].into_iter(), undirected.successors_fn());
+        assert_eq!(regions[0].len(), 4);
+
+        let mut directed = Graph::directed();
+        directed.add_edge('a', 'b', 5u64);
+        directed.add_edge('b', 'c', 1);
+        directed.add_edge('a', 'c', 9);
+        let (a, b) = (directed.index(&'a').unwrap(), directed.index(&'b').unwrap());
+        assert!(!directed.contains_edge(b, a));
+        assert_eq!(
+            (
+                directed.out_degree(a),
+                directed.in_degree(a),
+                directed.degree(b)
+            ),
+            (2, 0, 2)
+        );
+        let distances = min_distances(a, directed.weighted_edges_fn());
+        assert_eq!(distances[&directed.index(&'c').unwrap()], 6);
+
+        let map = HashMap::from([('x', vec!['y']), ('y', vec!['z'])]);
+        assert_eq!(find('x', |n| map.successors(&n), |_| true).count(), 3);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{cmp::Reverse, collections::BinaryHeap, hash::Hash};
+
+use bitvec::vec::BitVec;
+
+use super::{Adjacency, Graph, NodeIndex};
+
+/// Each node's neighbors in an undirected graph, as bitsets for fast intersection.
+fn neighbor_sets<N, E>(graph: &Graph<N, E>) -> Vec<BitVec>
+where
+    N: Eq + Hash + Clone,
+{
+    assert!(!graph.is_directed(), "Cliques need an undirected graph");
+    graph
+        .node_indices()
+        .map(|node| {
+            let mut set = BitVec::repeat(false, graph.len());
+            for neighbor in graph.successors(&node) {
+                if neighbor != node {
+                    set.set(neighbor, true);
+                }
+            }
+            set
+        })
+        .collect()
+}
+
+/// Orders nodes by repeatedly taking the one with the fewest neighbors among the nodes not yet
+/// taken. Each node then has few neighbors later in the order.
+pub fn degeneracy_order<N, E>(graph: &Graph<N, E>) -> Vec<NodeIndex>
+where
+    N: Eq + Hash + Clone,
+{
+    let mut degrees = graph
+        .node_indices()
+        .map(|node| graph.successors(&node).filter(|&n| n != node).count())
+        .collect::<Vec<_>>();
+    let mut taken = vec![false; graph.len()];
+    let mut queue = graph
+        .node_indices()
+        .map(|node| Reverse((degrees[node], node)))
+        .collect::<BinaryHeap<_>>();
+
+    let mut order = Vec::with_capacity(graph.len());
+    while let Some(Reverse((degree, node))) = queue.pop() {
+        // skip entries made stale by a neighbor being taken
+        if taken[node] || degree != degrees[node] {
+            continue;
+        }
+        taken[node] = true;
+        order.push(node);
+        for neighbor in graph.successors(&node) {
+            if !taken[neighbor] {
+                degrees[neighbor] -= 1;
+                queue.push(Reverse((degrees[neighbor], neighbor)));
+            }
+        }
+    }
+    order
+}
+
+/// Every clique which can't be extended by another node (Bron-Kerbosch with pivoting, over a
+/// degeneracy ordering). Each clique is sorted by node index.
+pub fn maximal_cliques<N, E>(graph: &Graph<N, E>) -> Vec<Vec<NodeIndex>>
+where
+    N: Eq + Hash + Clone,
+{
+    let mut cliques = vec![];
+    for_each_maximal_clique(graph, |clique| {
+        let mut clique = clique.to_vec();
+        clique.sort_unstable();
+        cliques.push(clique);
+    });
+    cliques
+}
+
+/// The largest clique, sorted by node index. If there are several, which is returned is
+/// unspecified.
+pub fn max_clique<N, E>(graph: &Graph<N, E>) -> Vec<NodeIndex>
+where
+    N: Eq + Hash + Clone,
+{
+    let mut largest: Vec<NodeIndex> = vec![];
+    for_each_maximal_clique(graph, |clique| {
+        if clique.len() > largest.len() {
+            largest = clique.to_vec();
+        }
+    });
+    largest.sort_unstable();
+    largest
+}
+
+fn for_each_maximal_clique<N, E>(graph: &Graph<N, E>, mut report: impl FnMut(&[NodeIndex]))
+where
+    N: Eq + Hash + Clone,
+{
+    let neighbors = neighbor_sets(graph);
+    let mut earlier = BitVec::repeat(false, graph.len());
+    let mut candidates = earlier.clone();
+    let mut excluded = earlier.clone();
+    let mut scratch = vec![];
+    for node in degeneracy_order(graph) {
+        difference_into(&mut candidates, &neighbors[node], &earlier);
+        intersect_into(&mut excluded, &neighbors[node], &earlier);
+        bron_kerbosch(
+            &neighbors,
+            &mut vec![node],
+            &mut candidates,
+            &mut excluded,
+            &mut scratch,
+            &mut report,
+        );
+        earlier.set(node, true);
+    }
+}
+
+/// The number of nodes in both sets, without building their intersection. Bits past the end
+/// of a neighbor set are always clear, so whole words can be compared.
+fn count_common(neighbors: &BitVec, set: &BitVec) -> usize {
+    neighbors
+        .as_raw_slice()
+        .iter()
+        .zip(set.as_raw_slice())
+        .map(|(a, b)| (a & b).count_ones() as usize)
+        .sum()
+}
+
+/// Sets `target` to the nodes in both `a` and `b`, a word at a time.
+fn intersect_into(target: &mut BitVec, a: &BitVec, b: &BitVec) {
+    for ((t, a), b) in target
+        .as_raw_mut_slice()
+        .iter_mut()
+        .zip(a.as_raw_slice())
+        .zip(b.as_raw_slice())
+    {
+        *t = a & b;
+    }
+}
+
+/// Sets `target` to the nodes in `a` but not `b`, a word at a time.
+fn difference_into(target: &mut BitVec, a: &BitVec, b: &BitVec) {
+    for ((t, a), b) in target
+        .as_raw_mut_slice()
+        .iter_mut()
+        .zip(a.as_raw_slice())
+        .zip(b.as_raw_slice())
+    {
+        *t = a & !b;
+    }
+}
+
+/// Reports every maximal clique containing all of `clique`, some of `candidates`, and none
+/// of `excluded`, leaving both changed. `scratch` keeps the sets for each level of the search
+/// between calls, so only the first visit to a new depth allocates.
+fn bron_kerbosch(
+    neighbors: &[BitVec],
+    clique: &mut Vec<NodeIndex>,
+    candidates: &mut BitVec,
+    excluded: &mut BitVec,
+    scratch: &mut Vec<[BitVec; 3]>,
+    report: &mut impl FnMut(&[NodeIndex]),
+) {
+    let Some(pivot) = candidates
+        .iter_ones()
+        .chain(excluded.iter_ones())
+        .max_by_key(|&u| count_common(&neighbors[u], candidates))
+    else {
+        report(clique);
+        return;
+    };
+
+    let depth = clique.len();
+    if scratch.len() < depth {
+        let empty = BitVec::repeat(false, candidates.len());
+        scratch.resize_with(depth, || [empty.clone(), empty.clone(), empty.clone()]);
+    }
+    // taken out while in use, so the deeper levels can borrow the rest
+    let [mut branches, mut next_candidates, mut next_excluded] =
+        std::mem::take(&mut scratch[depth - 1]);
+
+    // every maximal clique includes the pivot or one of its non-neighbors
+    difference_into(&mut branches, candidates, &neighbors[pivot]);
+    for node in branches.iter_ones() {
+        intersect_into(&mut next_candidates, candidates, &neighbors[node]);
+        intersect_into(&mut next_excluded, excluded, &neighbors[node]);
+        clique.push(node);
+        bron_kerbosch(
+            neighbors,
+            clique,
+            &mut next_candidates,
+            &mut next_excluded,
+            scratch,
+            report,
+        );
+        clique.pop();
+        candidates.set(node, false);
+        excluded.set(node, true);
+    }
+    scratch[depth - 1] = [branches, next_candidates, next_excluded];
+}
+
+/// Every clique of exactly `size` nodes (e.g. triangles for 3), each sorted by node index.
+pub fn cliques_of_size<N, E>(graph: &Graph<N, E>, size: usize) -> Vec<Vec<NodeIndex>>
+where
+    N: Eq + Hash + Clone,
+{
+    let neighbors = neighbor_sets(graph);
+    let mut result = vec![];
+    if size == 0 {
+        return result;
+    }
+    let all = BitVec::repeat(true, graph.len());
+    let mut scratch = vec![BitVec::repeat(false, graph.len()); size];
+    extend_cliques(
+        &neighbors,
+        &mut vec![],
+        &all,
+        &mut scratch,
+        size,
+        &mut result,
+    );
+    result
+}
+
+/// Adds cliques of `size` made from `clique` plus higher-indexed `candidates`. `scratch` holds
+/// the candidates for each deeper level.
+fn extend_cliques(
+    neighbors: &[BitVec],
+    clique: &mut Vec<NodeIndex>,
+    candidates: &BitVec,
+    scratch: &mut [BitVec],
+    size: usize,
+    result: &mut Vec<Vec<NodeIndex>>,
+) {
+    if clique.len() == size {
+        result.push(clique.clone());
+        return;
+    }
+    let (next_candidates, deeper) = scratch.split_first_mut().unwrap();
+    for node in candidates.iter_ones() {
+        intersect_into(next_candidates, candidates, &neighbors[node]);
+        next_candidates[..=node].fill(false);
+        if next_candidates.count_ones() + clique.len() + 1 < size {
+            continue;
+        }
+        clique.push(node);
+        extend_cliques(neighbors, clique, next_candidates, deeper, size, result);
+        clique.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{parse_edges, Directedness};
+
+    #[test]
+    fn test_cliques() {
+        // a 4-clique abcd, a triangle cde, and a pendant edge ef
+        let graph = Graph::from_edges(
+            Directedness::Undirected,
+            parse_edges("a-b\na-c\na-d\nb-c\nb-d\nc-d\nc-e\nd-e\ne-f", "-"),
+        );
+        let names =
+            |clique: &Vec<NodeIndex>| clique.iter().map(|&n| *graph.node(n)).collect::<String>();
+
+        assert_eq!(names(&max_clique(&graph)), "abcd");
+        let mut maximal = maximal_cliques(&graph)
+            .iter()
+            .map(names)
+            .collect::<Vec<_>>();
+        maximal.sort();
+        assert_eq!(maximal, vec!["abcd", "cde", "ef"]);
+        assert_eq!(cliques_of_size(&graph, 3).len(), 5);
+        assert_eq!(cliques_of_size(&graph, 4).len(), 1);
+        assert_eq!(cliques_of_size(&graph, 2).len(), graph.edge_count());
+        assert!(cliques_of_size(&graph, 5).is_empty());
+        assert_eq!(degeneracy_order(&graph)[0], graph.index(&"f").unwrap());
+    }
+}
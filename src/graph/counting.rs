@@ -0,0 +1,262 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+};
+
+use itertools::Itertools;
+
+use super::{shortest_path_dag_inner, toposort, Cycle, Weight};
+
+/// Why paths couldn't be counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathCountError<V> {
+    /// There are infinitely many paths, since they can go around this cycle.
+    Cycle(Cycle<V>),
+    /// There are more paths than fit in a u64.
+    Overflow,
+}
+
+impl<V> From<Cycle<V>> for PathCountError<V> {
+    fn from(cycle: Cycle<V>) -> Self {
+        PathCountError::Cycle(cycle)
+    }
+}
+
+fn checked_sum<V>(a: u64, b: u64) -> Result<u64, PathCountError<V>> {
+    a.checked_add(b).ok_or(PathCountError::Overflow)
+}
+
+/// Counts paths which end at a goal node, remembering the count from every node it passes
+/// through so that counting from many starts is cheap. A path may pass through a goal and
+/// continue to another; each of those is counted.
+pub struct PathCounter<V, G, E> {
+    goal: G,
+    edges: E,
+    // None while the node is on the path being explored
+    counts: HashMap<V, Option<u64>>,
+}
+
+impl<V, G, E, EI> PathCounter<V, G, E>
+where
+    V: Eq + Hash + Clone,
+    G: FnMut(&V) -> bool,
+    E: FnMut(&V) -> EI,
+    EI: IntoIterator<Item = V>,
+{
+    pub fn new(goal: G, edges: E) -> Self {
+        Self {
+            goal,
+            edges,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn count_from(&mut self, start: V) -> Result<u64, PathCountError<V>> {
+        if let Some(&Some(count)) = self.counts.get(&start) {
+            return Ok(count);
+        }
+        let result = self.explore(start);
+        if result.is_err() {
+            // the nodes abandoned on the path would otherwise look like a cycle next time
+            self.counts.retain(|_, count| count.is_some());
+        }
+        result
+    }
+
+    fn explore(&mut self, start: V) -> Result<u64, PathCountError<V>> {
+        self.counts.insert(start.clone(), None);
+        let start_count = (self.goal)(&start) as u64;
+        let start_edges = (self.edges)(&start).into_iter();
+        // the path being explored, with each node's successors left to explore and the
+        // number of paths from it so far
+        let mut stack = vec![(start, start_edges, start_count)];
+
+        while let Some((_, successors, count)) = stack.last_mut() {
+            let Some(next) = successors.next() else {
+                let (node, _, count) = stack.pop().unwrap();
+                self.counts.insert(node, Some(count));
+                match stack.last_mut() {
+                    Some((_, _, parent_count)) => {
+                        *parent_count = checked_sum(*parent_count, count)?
+                    }
+                    None => return Ok(count),
+                }
+                continue;
+            };
+
+            match self.counts.entry(next) {
+                Entry::Occupied(entry) => match *entry.get() {
+                    Some(next_count) => *count = checked_sum(*count, next_count)?,
+                    None => {
+                        let next = entry.key();
+                        let start = stack.iter().position(|(node, _, _)| node == next).unwrap();
+                        return Err(Cycle {
+                            nodes: stack.drain(start..).map(|(node, _, _)| node).collect(),
+                        }
+                        .into());
+                    }
+                },
+                Entry::Vacant(entry) => {
+                    let next = entry.key().clone();
+                    entry.insert(None);
+                    let next_count = (self.goal)(&next) as u64;
+                    let next_edges = (self.edges)(&next).into_iter();
+                    stack.push((next, next_edges, next_count));
+                }
+            }
+        }
+
+        unreachable!("The start's count is returned when it is popped")
+    }
+}
+
+/// The number of paths from the start which end at a goal node; see `PathCounter`.
+pub fn count_paths<V, EI>(
+    start: V,
+    goal: impl FnMut(&V) -> bool,
+    edges: impl FnMut(&V) -> EI,
+) -> Result<u64, PathCountError<V>>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    PathCounter::new(goal, edges).count_from(start)
+}
+
+/// The number of paths from the start to each reachable goal node.
+pub fn count_paths_to_goals<V, EI>(
+    start: V,
+    mut goal: impl FnMut(&V) -> bool,
+    mut edges: impl FnMut(&V) -> EI,
+) -> Result<HashMap<V, u64>, PathCountError<V>>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    let order = toposort([start.clone()], &mut edges)?;
+    let mut counts = HashMap::from([(start, 1)]);
+    for node in &order {
+        let count = counts[node];
+        for next in edges(node) {
+            let next_count = counts.entry(next).or_insert(0);
+            *next_count = checked_sum(*next_count, count)?;
+        }
+    }
+    counts.retain(|node, _| goal(node));
+    Ok(counts)
+}
+
+/// The shortest distance from the start to a goal node, and the number of distinct shortest
+/// paths to goals at that distance. None if no goal is reachable.
+pub fn count_shortest_paths<V, W, EI>(
+    start: V,
+    goal: impl Fn(&V) -> bool,
+    edges: impl FnMut(&V) -> EI,
+) -> Result<Option<(W, u64)>, PathCountError<V>>
+where
+    V: Eq + Hash + Clone,
+    W: Weight,
+    EI: IntoIterator<Item = (V, W)>,
+{
+    let dag = shortest_path_dag_inner(start.clone(), edges, |_| false);
+    let goals = dag
+        .distances()
+        .filter(|(node, _)| goal(node))
+        .collect::<Vec<_>>();
+    let Some(min_distance) =
+        goals
+            .iter()
+            .map(|&(_, distance)| distance)
+            .reduce(|a, b| if b < a { b } else { a })
+    else {
+        return Ok(None);
+    };
+
+    // count backwards from each goal to the start along predecessors
+    let mut counter = PathCounter::new(
+        |node: &V| *node == start,
+        |node: &V| dag.predecessors(node).cloned().collect_vec(),
+    );
+    let mut total = 0;
+    for (node, distance) in goals {
+        if distance == min_distance {
+            total = checked_sum(total, counter.count_from(node.clone())?)?;
+        }
+    }
+    Ok(Some((min_distance, total)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_count_paths() {
+        // a grid of lattice paths moving right or down to (3, 3), which has 6 choose 3 of them
+        let edges = |&(x, y): &(u8, u8)| {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| x <= 3 && y <= 3)
+        };
+        assert_eq!(count_paths((0, 0), |&n| n == (3, 3), edges), Ok(20));
+        assert_eq!(count_paths((3, 3), |&n| n == (0, 0), edges), Ok(0));
+
+        let to_goals = count_paths_to_goals((0, 0), |&(x, _)| x == 3, edges).unwrap();
+        assert_eq!(to_goals.len(), 4);
+        assert_eq!(to_goals[&(3, 0)], 1);
+        assert_eq!(to_goals[&(3, 3)], 20);
+
+        // with free diagonal moves, only the diagonal path is shortest
+        let weighted = |&(x, y): &(u8, u8)| {
+            [((x + 1, y), 1), ((x, y + 1), 1), ((x + 1, y + 1), 1)]
+                .into_iter()
+                .filter(|&((x, y), _)| x <= 3 && y <= 3)
+        };
+        assert_eq!(
+            count_shortest_paths((0, 0), |&n| n == (3, 3), weighted),
+            Ok(Some((3u64, 1)))
+        );
+        assert_eq!(
+            count_shortest_paths((0, 0), |&n| n == (3, 1), weighted),
+            Ok(Some((3, 3)))
+        );
+    }
+
+    #[test]
+    fn test_count_paths_errors() {
+        let cyclic = |&n: &u8| match n {
+            0 => vec![1],
+            1 => vec![2],
+            2 => vec![1, 3],
+            _ => vec![],
+        };
+        let Err(PathCountError::Cycle(cycle)) = count_paths(0, |&n| n == 3, cyclic) else {
+            panic!("Expected a cycle");
+        };
+        assert_eq!(cycle.nodes, vec![1, 2]);
+        assert!(count_paths_to_goals(0, |&n| n == 3, cyclic).is_err());
+        // a counter stays usable after finding a cycle
+        let mut counter = PathCounter::new(|&n| n == 3, cyclic);
+        assert!(counter.count_from(0).is_err());
+        assert_eq!(counter.count_from(3), Ok(1));
+        assert!(matches!(
+            counter.count_from(0),
+            Err(PathCountError::Cycle(_))
+        ));
+
+        // each layer doubles the paths
+        let layers = |&(layer, _): &(u8, bool)| {
+            [(layer + 1, false), (layer + 1, true)]
+                .into_iter()
+                .filter(|&(layer, _)| layer <= 70)
+        };
+        assert_eq!(
+            count_paths((0, false), |&(layer, _)| layer == 63, layers),
+            Ok(1 << 63)
+        );
+        assert_eq!(
+            count_paths((0, false), |&(layer, _)| layer == 64, layers),
+            Err(PathCountError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::Hash,
+};
+
+use itertools::Itertools;
+
+use super::{Adjacency, Graph, NodeIndex};
+
+/// A cycle of edges, which makes a topological order impossible. The first node follows the
+/// last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<V> {
+    pub nodes: Vec<V>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    InProgress,
+    Done,
+}
+
+/// Orders the nodes, and every node reachable from them, so that each comes before all of its
+/// successors. Fails with one of the cycles if there is no such order.
+pub fn toposort<V, EI>(
+    nodes: impl IntoIterator<Item = V>,
+    mut edges: impl FnMut(&V) -> EI,
+) -> Result<Vec<V>, Cycle<V>>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    let mut marks = HashMap::new();
+    let mut order = vec![];
+
+    for root in nodes {
+        if marks.contains_key(&root) {
+            continue;
+        }
+        marks.insert(root.clone(), Mark::InProgress);
+        let root_edges = edges(&root).into_iter();
+        // the path being explored, with the successors of each node left to explore
+        let mut stack = vec![(root, root_edges)];
+
+        while let Some((_, successors)) = stack.last_mut() {
+            let Some(next) = successors.next() else {
+                let (node, _) = stack.pop().unwrap();
+                marks.insert(node.clone(), Mark::Done);
+                order.push(node);
+                continue;
+            };
+            match marks.get(&next) {
+                Some(Mark::Done) => {}
+                Some(Mark::InProgress) => {
+                    let start = stack.iter().position(|(node, _)| *node == next).unwrap();
+                    return Err(Cycle {
+                        nodes: stack.drain(start..).map(|(node, _)| node).collect(),
+                    });
+                }
+                None => {
+                    marks.insert(next.clone(), Mark::InProgress);
+                    let next_edges = edges(&next).into_iter();
+                    stack.push((next, next_edges));
+                }
+            }
+        }
+    }
+
+    order.reverse();
+    Ok(order)
+}
+
+/// Whether the nodes, and every node reachable from them, are free of cycles.
+pub fn is_dag<V, EI>(nodes: impl IntoIterator<Item = V>, edges: impl FnMut(&V) -> EI) -> bool
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    toposort(nodes, edges).is_ok()
+}
+
+/// Orders just the given nodes by the edges among them, so that each comes before its
+/// successors in the subgraph they induce (Kahn's algorithm). Edges to nodes outside the subset
+/// are ignored, along with any order they imply: with edges `a -> b -> c`, `[c, a]` is left as
+/// it is. That suits relations which are only meaningful within each subset, like page rules
+/// which are cyclic as a whole. Nodes the edges don't order keep their relative order from the
+/// input. Fails with one of the cycles among the nodes if there is no such order.
+pub fn toposort_induced<V, EI>(
+    nodes: impl IntoIterator<Item = V>,
+    mut edges: impl FnMut(&V) -> EI,
+) -> Result<Vec<V>, Cycle<V>>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    let nodes = nodes.into_iter().unique().collect_vec();
+    let positions: HashMap<V, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(position, node)| (node.clone(), position))
+        .collect();
+    let successors = nodes
+        .iter()
+        .map(|node| {
+            edges(node)
+                .into_iter()
+                .filter_map(|next| positions.get(&next).copied())
+                .collect_vec()
+        })
+        .collect_vec();
+    toposort_positions(&successors, |position| nodes[position].clone())
+}
+
+/// Like `toposort_induced`, for nodes of a `Graph`. Positions are looked up by node index
+/// rather than hashed, which is much quicker when ordering many small subsets of one graph.
+pub fn graph_toposort_induced<N, E>(
+    graph: &Graph<N, E>,
+    nodes: &[NodeIndex],
+) -> Result<Vec<NodeIndex>, Cycle<NodeIndex>>
+where
+    N: Eq + Hash + Clone,
+{
+    let mut positions = vec![None; graph.len()];
+    let mut unique = Vec::with_capacity(nodes.len());
+    for &node in nodes {
+        if positions[node].is_none() {
+            positions[node] = Some(unique.len());
+            unique.push(node);
+        }
+    }
+    let successors = unique
+        .iter()
+        .map(|node| {
+            graph
+                .successors(node)
+                .filter_map(|next| positions[next])
+                .collect_vec()
+        })
+        .collect_vec();
+    toposort_positions(&successors, |position| unique[position])
+}
+
+/// Kahn's algorithm over the nodes `0..successors.len()`, breaking ties by position. The order
+/// and any cycle are reported through `node`.
+fn toposort_positions<V>(
+    successors: &[Vec<usize>],
+    node: impl Fn(usize) -> V,
+) -> Result<Vec<V>, Cycle<V>> {
+    let mut predecessor_counts = vec![0; successors.len()];
+    for &next in successors.iter().flatten() {
+        predecessor_counts[next] += 1;
+    }
+    // the earliest ready node in the input goes next
+    let mut ready = (0..successors.len())
+        .filter(|&position| predecessor_counts[position] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut order = Vec::with_capacity(successors.len());
+    while let Some(Reverse(position)) = ready.pop() {
+        order.push(node(position));
+        for &next in &successors[position] {
+            predecessor_counts[next] -= 1;
+            if predecessor_counts[next] == 0 {
+                ready.push(Reverse(next));
+            }
+        }
+    }
+
+    if order.len() == successors.len() {
+        return Ok(order);
+    }
+    // the nodes left over are on or after a cycle
+    let remaining = (0..successors.len()).filter(|&position| predecessor_counts[position] > 0);
+    let cycle = toposort(remaining, |&position| successors[position].clone())
+        .expect_err("Unordered nodes should include a cycle");
+    Err(Cycle {
+        nodes: cycle.nodes.into_iter().map(node).collect(),
+    })
+}
+
+/// Whether no node in the sequence has an edge to a node earlier in it.
+pub fn respects_order<V, EI>(sequence: &[V], mut edges: impl FnMut(&V) -> EI) -> bool
+where
+    V: Eq + Hash,
+    EI: IntoIterator<Item = V>,
+{
+    let positions: HashMap<&V, usize> = sequence
+        .iter()
+        .enumerate()
+        .map(|(position, node)| (node, position))
+        .collect();
+    sequence.iter().enumerate().all(|(position, node)| {
+        edges(node)
+            .into_iter()
+            .all(|next| positions.get(&next).is_none_or(|&p| p > position))
+    })
+}
+
+/// Like `respects_order`, for nodes of a `Graph`, looking positions up by node index.
+pub fn graph_respects_order<N, E>(graph: &Graph<N, E>, sequence: &[NodeIndex]) -> bool
+where
+    N: Eq + Hash + Clone,
+{
+    let mut positions = vec![None; graph.len()];
+    for (position, &node) in sequence.iter().enumerate() {
+        positions[node] = Some(position);
+    }
+    sequence.iter().enumerate().all(|(position, node)| {
+        graph
+            .successors(node)
+            .all(|next| positions[next].is_none_or(|p| p > position))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::Directedness;
+
+    fn edges(node: &u8) -> Vec<u8> {
+        match node {
+            1 => vec![2, 3],
+            2 => vec![4],
+            3 => vec![4],
+            4 => vec![5],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_toposort() {
+        let order = toposort([1], edges).unwrap();
+        assert_eq!(order.len(), 5);
+        assert!(respects_order(&order, edges));
+        assert!(!respects_order(&[4, 2], edges));
+
+        // 3 only reaches 5 through 4, which is left out, so they keep their input order
+        assert_eq!(toposort_induced([5, 3, 1], edges).unwrap(), vec![5, 1, 3]);
+        assert_eq!(toposort_induced([4, 6, 2], edges).unwrap(), vec![6, 2, 4]);
+
+        let cyclic = |node: &u8| match node {
+            4 => vec![2],
+            n => edges(n),
+        };
+        assert!(!is_dag([1], cyclic));
+        let cycle = toposort([1], cyclic).unwrap_err();
+        assert_eq!(cycle.nodes.iter().sorted().collect_vec(), vec![&2, &4]);
+        let cycle = toposort_induced([5, 4, 3, 2], cyclic).unwrap_err();
+        assert_eq!(cycle.nodes.iter().sorted().collect_vec(), vec![&2, &4]);
+        assert!(toposort_induced([4, 3, 1], cyclic).is_ok());
+    }
+
+    #[test]
+    fn test_graph_induced() {
+        let mut graph = Graph::from_edges(
+            Directedness::Directed,
+            [(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)],
+        );
+        let [one, two, three, four, five] = [1, 2, 3, 4, 5].map(|n| graph.index(&n).unwrap());
+        // a node with no edges is unconstrained
+        let six = graph.intern(6);
+
+        assert!(graph_respects_order(&graph, &[one, six, four]));
+        assert!(!graph_respects_order(&graph, &[four, six, three]));
+        assert_eq!(
+            graph_toposort_induced(&graph, &[five, four, six, three]).unwrap(),
+            toposort_induced([five, four, six, three], graph.successors_fn()).unwrap()
+        );
+        assert_eq!(
+            graph_toposort_induced(&graph, &[four, six, two]).unwrap(),
+            vec![six, two, four]
+        );
+    }
+}
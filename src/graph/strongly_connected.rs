@@ -0,0 +1,300 @@
+use std::{collections::HashMap, hash::Hash, iter, mem, slice};
+
+use itertools::Itertools;
+
+use super::Adjacency;
+
+/// The nodes found by Tarjan's algorithm, by the order it reached them, with their edges and
+/// strongly connected components (as lists of those indices) in reverse topological order.
+struct Tarjan<V> {
+    nodes: Vec<V>,
+    successors: Vec<Vec<usize>>,
+    components: Vec<Vec<usize>>,
+}
+
+fn tarjan<V, EI>(nodes: impl IntoIterator<Item = V>, mut edges: impl FnMut(&V) -> EI) -> Tarjan<V>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    let mut result = Tarjan {
+        nodes: vec![],
+        successors: vec![],
+        components: vec![],
+    };
+    let mut indexes: HashMap<V, usize> = HashMap::new();
+    // the smallest index reachable from each node through nodes still on the stack
+    let mut low_links: Vec<usize> = vec![];
+    let mut on_stack: Vec<bool> = vec![];
+    let mut stack = vec![];
+
+    for root in nodes {
+        if indexes.contains_key(&root) {
+            continue;
+        }
+        // the path being explored, with the successors of each node left to explore
+        let mut path = vec![];
+        let mut next = Some(root);
+
+        loop {
+            if let Some(node) = next.take() {
+                let index = result.nodes.len();
+                indexes.insert(node.clone(), index);
+                low_links.push(index);
+                on_stack.push(true);
+                stack.push(index);
+                result.successors.push(vec![]);
+                path.push((index, edges(&node).into_iter()));
+                result.nodes.push(node);
+            }
+
+            let Some((index, successors)) = path.last_mut() else {
+                break;
+            };
+            let index = *index;
+            if let Some(successor) = successors.next() {
+                match indexes.get(&successor) {
+                    Some(&successor_index) => {
+                        if on_stack[successor_index] {
+                            low_links[index] = low_links[index].min(successor_index);
+                        }
+                        result.successors[index].push(successor_index);
+                    }
+                    None => {
+                        result.successors[index].push(result.nodes.len());
+                        next = Some(successor);
+                    }
+                }
+                continue;
+            }
+
+            path.pop();
+            if let Some(&(parent, _)) = path.last() {
+                low_links[parent] = low_links[parent].min(low_links[index]);
+            }
+            if low_links[index] == index {
+                let start = stack.iter().rposition(|&i| i == index).unwrap();
+                let component = stack.split_off(start);
+                for &member in &component {
+                    on_stack[member] = false;
+                }
+                result.components.push(component);
+            }
+        }
+    }
+
+    result
+}
+
+/// The strongly connected components of the nodes and every node reachable from them (Tarjan's
+/// algorithm): groups in which every node can reach every other. Components come after every
+/// component they can reach.
+pub fn strongly_connected_components<V, EI>(
+    nodes: impl IntoIterator<Item = V>,
+    edges: impl FnMut(&V) -> EI,
+) -> Vec<Vec<V>>
+where
+    V: Eq + Hash + Clone,
+    EI: IntoIterator<Item = V>,
+{
+    let Tarjan {
+        nodes, components, ..
+    } = tarjan(nodes, edges);
+    components
+        .into_iter()
+        .map(|component| component.into_iter().map(|i| nodes[i].clone()).collect())
+        .collect()
+}
+
+/// A graph's strongly connected components, as nodes of a DAG with an edge wherever the
+/// original graph had an edge between components.
+#[derive(Debug, Clone)]
+pub struct Condensation<V> {
+    // in topological order
+    components: Vec<Vec<V>>,
+    component_indexes: HashMap<V, usize>,
+    // sorted, without duplicates
+    successors: Vec<Vec<usize>>,
+}
+
+impl<V> Condensation<V>
+where
+    V: Eq + Hash + Clone,
+{
+    /// Condenses the nodes and every node reachable from them.
+    pub fn new<EI>(nodes: impl IntoIterator<Item = V>, edges: impl FnMut(&V) -> EI) -> Self
+    where
+        EI: IntoIterator<Item = V>,
+    {
+        let tarjan = tarjan(nodes, edges);
+        let count = tarjan.components.len();
+        // Tarjan's algorithm finds components in reverse topological order
+        let mut component_of_index = vec![0; tarjan.nodes.len()];
+        for (i, component) in tarjan.components.iter().enumerate() {
+            for &member in component {
+                component_of_index[member] = count - 1 - i;
+            }
+        }
+
+        let mut successors = vec![vec![]; count];
+        for (index, index_successors) in tarjan.successors.iter().enumerate() {
+            let component = component_of_index[index];
+            successors[component].extend(
+                index_successors
+                    .iter()
+                    .map(|&next| component_of_index[next])
+                    .filter(|&next| next != component),
+            );
+        }
+        for component_successors in &mut successors {
+            component_successors.sort_unstable();
+            component_successors.dedup();
+        }
+
+        let component_indexes = tarjan
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.clone(), component_of_index[index]))
+            .collect();
+        let components = tarjan
+            .components
+            .into_iter()
+            .rev()
+            .map(|component| {
+                component
+                    .into_iter()
+                    .map(|index| tarjan.nodes[index].clone())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            components,
+            component_indexes,
+            successors,
+        }
+    }
+
+    /// The number of components.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Components in topological order: each comes before every component it can reach.
+    pub fn components(&self) -> &[Vec<V>] {
+        &self.components
+    }
+
+    pub fn component(&self, index: usize) -> &[V] {
+        &self.components[index]
+    }
+
+    /// The index of the node's component, or None if the node wasn't reached.
+    pub fn component_of(&self, node: &V) -> Option<usize> {
+        self.component_indexes.get(node).copied()
+    }
+
+    /// The indices of every component reachable from this one, including itself.
+    pub fn reachable_components(&self, index: usize) -> Vec<usize> {
+        let mut reached = vec![false; self.len()];
+        let mut queue = vec![index];
+        reached[index] = true;
+        while let Some(now) = queue.pop() {
+            for &next in &self.successors[now] {
+                if !reached[next] {
+                    reached[next] = true;
+                    queue.push(next);
+                }
+            }
+        }
+        reached.iter().positions(|&r| r).collect()
+    }
+
+    /// Whether there's a path from one node to the other. Every node reaches itself.
+    pub fn reaches(&self, from: &V, to: &V) -> bool {
+        let (Some(from), Some(to)) = (self.component_of(from), self.component_of(to)) else {
+            return false;
+        };
+        // components only have edges to later components
+        let mut reached = vec![false; self.len()];
+        let mut queue = vec![from];
+        while let Some(now) = queue.pop() {
+            if now == to {
+                return true;
+            }
+            for &next in &self.successors[now] {
+                if next <= to && !mem::replace(&mut reached[next], true) {
+                    queue.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Every node reachable from this one, including itself.
+    pub fn reachable_from(&self, node: &V) -> impl Iterator<Item = &V> {
+        self.component_of(node)
+            .map(|index| self.reachable_components(index))
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|index| self.components[index].iter())
+    }
+}
+
+/// The DAG between components, by component index.
+impl<V> Adjacency<usize> for Condensation<V> {
+    type Successors<'a>
+        = iter::Copied<slice::Iter<'a, usize>>
+    where
+        Self: 'a;
+
+    fn successors(&self, component: &usize) -> Self::Successors<'_> {
+        self.successors[*component].iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::toposort;
+
+    // two cycles, 1-2-3 and 4-5, with 3 -> 4, plus 6 -> 1
+    fn edges(node: &u8) -> Vec<u8> {
+        match node {
+            1 => vec![2],
+            2 => vec![3],
+            3 => vec![1, 4],
+            4 => vec![5],
+            5 => vec![4],
+            6 => vec![1],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let components = strongly_connected_components([1], edges)
+            .into_iter()
+            .map(|c| c.into_iter().sorted().collect_vec())
+            .collect_vec();
+        assert_eq!(components, vec![vec![4, 5], vec![1, 2, 3]]);
+
+        let condensation = Condensation::new([1, 6], edges);
+        assert_eq!(condensation.len(), 3);
+        assert_eq!(condensation.component(0), &[6]);
+        assert!(condensation.reaches(&6, &5));
+        assert!(condensation.reaches(&2, &1));
+        assert!(!condensation.reaches(&4, &1));
+        assert!(!condensation.reaches(&1, &7));
+        assert_eq!(condensation.reachable_from(&3).count(), 5);
+        assert_eq!(
+            toposort(0..condensation.len(), condensation.successors_fn()).unwrap(),
+            vec![0, 1, 2]
+        );
+    }
+}
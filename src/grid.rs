@@ -1,13 +1,68 @@
 use crate::prelude::*;
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    marker::PhantomData,
+};
+
+mod automaton;
+mod components;
+mod grid3;
+mod hex;
+mod lattice;
+mod lines;
+mod pattern;
+mod recording;
+mod search;
+mod sets;
+pub use automaton::*;
+pub use components::*;
+pub use grid3::*;
+pub use hex::*;
+pub use lattice::*;
+pub use lines::*;
+pub use pattern::*;
+pub use recording::*;
+pub use sets::*;
 
 pub type Index = isize;
 
-#[derive(Debug, Clone)]
-pub struct Grid<T> {
+/// How a grid treats locations past its edges. This is a type parameter of `Grid` rather
+/// than a field, so that bounded grids don't pay for wrapping on every lookup.
+pub trait Topology: Debug + Clone + Copy + PartialEq + Eq + Hash + Default + Send + Sync {
+    /// Maps a location outside a grid of this size onto it, or None if it doesn't exist.
+    fn wrap(location: Location, width: usize, height: usize) -> Option<Location>;
+}
+
+/// Locations outside the grid don't exist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bounded;
+
+impl Topology for Bounded {
+    fn wrap(_location: Location, _width: usize, _height: usize) -> Option<Location> {
+        None
+    }
+}
+
+/// Locations wrap around at the edges, as on a torus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Toroidal;
+
+impl Topology for Toroidal {
+    fn wrap(location: Location, width: usize, height: usize) -> Option<Location> {
+        Some(Location::new(
+            location.x.rem_euclid(width as Index),
+            location.y.rem_euclid(height as Index),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T, Tp = Bounded> {
     contents: Vec<T>,
     width: usize,
     height: usize,
+    topology: PhantomData<Tp>,
 }
 
 impl<T> Grid<T> {
@@ -19,36 +74,60 @@ impl<T> Grid<T> {
             contents: vec![element; width * height],
             width,
             height,
+            topology: PhantomData,
         }
     }
+}
 
+impl<T, Tp: Topology> Grid<T, Tp> {
+    /// The same grid with a different treatment of its edges, e.g.
+    /// `grid.with_topology::<Toroidal>()`.
+    pub fn with_topology<U: Topology>(self) -> Grid<T, U> {
+        Grid {
+            contents: self.contents,
+            width: self.width,
+            height: self.height,
+            topology: PhantomData,
+        }
+    }
+
+    fn in_bounds(&self, location: Location) -> bool {
+        location.x >= 0
+            && location.y >= 0
+            && location.x < self.width as Index
+            && location.y < self.height as Index
+    }
+
+    /// Maps a location onto the grid: wrapped around for toroidal grids, or None if
+    /// it is out of bounds of a bounded grid.
+    pub fn normalize(&self, location: Location) -> Option<Location> {
+        if self.in_bounds(location) {
+            Some(location)
+        } else {
+            Tp::wrap(location, self.width, self.height)
+        }
+    }
+
+    /// The index into `contents` of a location which is already on the grid.
     fn index(&self, location: Location) -> usize {
-        assert!(
-            location.x >= 0
-                && location.y >= 0
-                && location.x < self.width as Index
-                && location.y < self.height as Index
-        );
+        assert!(self.in_bounds(location), "Location should be in bounds");
         location.y as usize * self.width + location.x as usize
     }
 
     /// Returns a cell for this location, or None if the location is out of bounds.
-    pub fn cell(&self, location: Location) -> Option<Cell<T>> {
-        if location.x < 0
-            || location.y < 0
-            || location.x >= self.width as Index
-            || location.y >= self.height as Index
-        {
-            None
-        } else {
-            Some(Cell {
-                grid: self,
-                location,
-            })
-        }
+    /// On a toroidal grid this always succeeds.
+    pub fn cell(&self, location: Location) -> Option<Cell<T, Tp>> {
+        self.normalize(location).map(|location| Cell {
+            grid: self,
+            location,
+        })
     }
 
+    /// Sets the contents at this location, which wraps around on a toroidal grid.
     pub fn set(&mut self, location: Location, value: T) {
+        let location = self
+            .normalize(location)
+            .expect("Location should be in bounds");
         let index = self.index(location);
         self.contents[index] = value;
     }
@@ -61,7 +140,7 @@ impl<T> Grid<T> {
         self.height
     }
 
-    pub fn cells(&self) -> impl Iterator<Item = Cell<T>> + Clone {
+    pub fn cells(&self) -> impl Iterator<Item = Cell<T, Tp>> + Clone {
         let width = self.width as Index;
         let height = self.height as Index;
         (0..height)
@@ -72,22 +151,128 @@ impl<T> Grid<T> {
             })
     }
 
-    pub fn map<U>(&self, mut f: impl FnMut(Cell<T>) -> U) -> Grid<U> {
-        let mut contents = Vec::with_capacity(self.width * self.height);
+    /// All locations on the grid in row-major order.
+    pub fn locations(&self) -> impl Iterator<Item = Location> + Clone {
+        let width = self.width as Index;
+        let height = self.height as Index;
+        (0..height).flat_map(move |y| (0..width).map(move |x| Location::new(x, y)))
+    }
+
+    pub fn get(&self, location: Location) -> Option<&T> {
+        self.normalize(location)
+            .map(|location| &self.contents[location.y as usize * self.width + location.x as usize])
+    }
+
+    /// The first location (in row-major order) whose contents match the predicate.
+    pub fn position(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Location> {
+        self.locations()
+            .find(|&location| predicate(&self[location]))
+    }
+
+    /// Adjacent locations which are on the grid (wrapped around on a toroidal grid).
+    pub fn neighbors(
+        &self,
+        location: Location,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = Location> + '_ {
+        connectivity
+            .neighbors(location)
+            .filter_map(|location| self.normalize(location))
+    }
+
+    /// Cardinally adjacent locations on the grid whose contents are passable. This works
+    /// directly with `graph` searches keyed by `Location`.
+    pub fn passable_neighbors<'a>(
+        &'a self,
+        location: Location,
+        mut passable: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = Location> + 'a {
+        self.neighbors(location, Connectivity::Four)
+            .filter(move |&location| passable(&self[location]))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(Cell<T, Tp>) -> U) -> Grid<U, Tp> {
+        let mut result = Grid {
+            contents: Vec::with_capacity(self.width * self.height),
+            width: self.width,
+            height: self.height,
+            topology: PhantomData,
+        };
+        self.map_into(&mut result, f);
+        result
+    }
+
+    /// Like `map`, but reuses the storage of `target`, replacing all of its contents.
+    pub fn map_into<U>(&self, target: &mut Grid<U, Tp>, mut f: impl FnMut(Cell<T, Tp>) -> U) {
+        target.contents.clear();
         for y in 0..self.height {
             for x in 0..self.width {
-                contents.push(f(Cell {
+                target.contents.push(f(Cell {
                     grid: self,
                     location: Location::new(x as Index, y as Index),
                 }));
             }
         }
+        target.width = self.width;
+        target.height = self.height;
+    }
 
-        Grid {
-            contents,
-            width: self.width,
-            height: self.height,
+    /// Every location whose contents differ between the two grids, as (location, ours, theirs).
+    pub fn diff(&self, other: &Grid<T, Tp>) -> Vec<(Location, T, T)>
+    where
+        T: PartialEq + Clone,
+    {
+        assert_eq!((self.width, self.height), (other.width, other.height));
+        self.locations()
+            .filter(|&location| self[location] != other[location])
+            .map(|location| (location, self[location].clone(), other[location].clone()))
+            .collect()
+    }
+
+    /// A copy of the part of the grid within `bounds` (`max` is exclusive).
+    pub fn crop(&self, bounds: Box2D<Index>) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let size = bounds.size();
+        Grid::new((), size.width as usize, size.height as usize)
+            .map(|c| self[c.location() + bounds.min.to_vector()].clone())
+    }
+
+    /// Renders each cell as a character, e.g. for printing with `{}`.
+    pub fn render(&self, f: impl FnMut(Cell<T, Tp>) -> char) -> Grid<char, Tp> {
+        self.map(f)
+    }
+
+    /// Draws `highlight` over each of the given locations (e.g. a path or visited set).
+    /// Locations off the grid are ignored.
+    pub fn overlay(mut self, locations: impl IntoIterator<Item = Location>, highlight: T) -> Self
+    where
+        T: Clone,
+    {
+        for location in locations {
+            if self.cell(location).is_some() {
+                self.set(location, highlight.clone());
+            }
         }
+        self
+    }
+}
+
+/// Indexing requires a location on the grid, even on a toroidal grid; use `get` or `cell`
+/// to wrap around.
+impl<T, Tp: Topology> std::ops::Index<Location> for Grid<T, Tp> {
+    type Output = T;
+
+    fn index(&self, location: Location) -> &T {
+        &self.contents[Grid::index(self, location)]
+    }
+}
+
+impl<T, Tp: Topology> std::ops::IndexMut<Location> for Grid<T, Tp> {
+    fn index_mut(&mut self, location: Location) -> &mut T {
+        let index = Grid::index(self, location);
+        &mut self.contents[index]
     }
 }
 
@@ -115,17 +300,28 @@ impl Grid<char> {
             contents,
             width: width.unwrap_or_default(),
             height,
+            topology: PhantomData,
         }
     }
 }
 
-impl Display for Grid<char> {
+/// Cells are right-aligned to the widest cell, and separated by spaces if any cell is
+/// wider than one character (so a `Grid<char>` prints exactly as its input lines).
+impl<T: Display, Tp> Display for Grid<T, Tp> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.contents.chunks(self.width) {
-            for c in row {
-                write!(f, "{}", c)?;
-            }
-            writeln!(f)?;
+        let rendered = self.contents.iter().map(|c| c.to_string()).collect_vec();
+        let column_width = rendered
+            .iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or_default();
+        let separator = if column_width > 1 { " " } else { "" };
+        for row in rendered.chunks(self.width) {
+            let row = row
+                .iter()
+                .map(|s| format!("{:>column_width$}", s))
+                .join(separator);
+            writeln!(f, "{}", row)?;
         }
         Ok(())
     }
@@ -157,66 +353,285 @@ pub fn cardinal_neighbors(l: Location) -> impl Iterator<Item = Location> {
     cardinal_headings().map(move |heading| l + heading)
 }
 
+pub fn manhattan_distance(a: Location, b: Location) -> usize {
+    let delta = a - b;
+    delta.x.unsigned_abs() + delta.y.unsigned_abs()
+}
+
+/// The number of king moves (including diagonals) between the locations.
+pub fn chebyshev_distance(a: Location, b: Location) -> usize {
+    let delta = a - b;
+    delta.x.unsigned_abs().max(delta.y.unsigned_abs())
+}
+
+/// Orders locations by row, then by column, matching the order of `Grid::cells`.
+pub fn row_major_order(a: &Location, b: &Location) -> std::cmp::Ordering {
+    a.y.cmp(&b.y).then(a.x.cmp(&b.x))
+}
+
+/// A compass direction. The cardinal directions come first so that `index` fits in
+/// two bits when only those are used.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+    NorthEast = 4,
+    SouthEast = 5,
+    SouthWest = 6,
+    NorthWest = 7,
+}
+
+impl Direction {
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// All eight directions, clockwise from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// Compact index: 0..4 for cardinal directions, 4..8 for diagonals.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => Direction::North,
+            1 => Direction::East,
+            2 => Direction::South,
+            3 => Direction::West,
+            4 => Direction::NorthEast,
+            5 => Direction::SouthEast,
+            6 => Direction::SouthWest,
+            7 => Direction::NorthWest,
+            _ => panic!("Invalid direction index {}", index),
+        }
+    }
+
+    pub fn is_cardinal(self) -> bool {
+        self.index() < 4
+    }
+
+    /// Rotates clockwise by the given number of eighths of a turn (negative for counter-clockwise).
+    pub fn rotate(self, eighths: isize) -> Self {
+        // Position in `ALL`: cardinals sit at the even slots and diagonals at the odd ones.
+        let index = self.index() as isize;
+        let position = if index < 4 {
+            2 * index
+        } else {
+            2 * (index - 4) + 1
+        };
+        Self::ALL[(position + eighths).rem_euclid(8) as usize]
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(2)
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(-2)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate(4)
+    }
+
+    pub fn heading(self) -> Heading {
+        match self {
+            Direction::North => NORTH,
+            Direction::East => EAST,
+            Direction::South => SOUTH,
+            Direction::West => WEST,
+            Direction::NorthEast => NORTH + EAST,
+            Direction::SouthEast => SOUTH + EAST,
+            Direction::SouthWest => SOUTH + WEST,
+            Direction::NorthWest => NORTH + WEST,
+        }
+    }
+
+    /// The direction of a unit heading (including diagonals), or None for any other vector.
+    pub fn from_heading(heading: Heading) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.heading() == heading)
+    }
+
+    /// Parses an arrow (`^v<>`), compass letter (`NSEW`) or `UDLR` character.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '^' | 'N' | 'U' => Some(Direction::North),
+            '>' | 'E' | 'R' => Some(Direction::East),
+            'v' | 'S' | 'D' => Some(Direction::South),
+            '<' | 'W' | 'L' => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    /// `^v<>` for cardinal directions, unicode arrows for diagonals.
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::East => '>',
+            Direction::South => 'v',
+            Direction::West => '<',
+            Direction::NorthEast => '↗',
+            Direction::SouthEast => '↘',
+            Direction::SouthWest => '↙',
+            Direction::NorthWest => '↖',
+        }
+    }
+
+    pub fn compass(self) -> &'static str {
+        match self {
+            Direction::North => "N",
+            Direction::East => "E",
+            Direction::South => "S",
+            Direction::West => "W",
+            Direction::NorthEast => "NE",
+            Direction::SouthEast => "SE",
+            Direction::SouthWest => "SW",
+            Direction::NorthWest => "NW",
+        }
+    }
+
+    /// `UDLR` letter, or None for diagonals.
+    pub fn udlr(self) -> Option<char> {
+        match self {
+            Direction::North => Some('U'),
+            Direction::East => Some('R'),
+            Direction::South => Some('D'),
+            Direction::West => Some('L'),
+            _ => None,
+        }
+    }
+}
+
+impl From<Direction> for Heading {
+    fn from(direction: Direction) -> Self {
+        direction.heading()
+    }
+}
+
+impl TryFrom<Heading> for Direction {
+    type Error = Heading;
+
+    fn try_from(heading: Heading) -> Result<Self, Self::Error> {
+        Direction::from_heading(heading).ok_or(heading)
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.compass())
+    }
+}
+
 #[derive(Debug)]
-pub struct Cell<'a, T> {
-    grid: &'a Grid<T>,
+pub struct Cell<'a, T, Tp = Bounded> {
+    grid: &'a Grid<T, Tp>,
     location: Location,
 }
 
-impl<'a, T> Cell<'a, T> {
+impl<'a, T, Tp: Topology> Cell<'a, T, Tp> {
     pub fn contents(&self) -> &T {
-        self.grid
-            .contents
-            .get(self.grid.index(self.location))
-            .expect("Cell should always be in bounds")
+        // cell locations are always normalized
+        &self.grid.contents[self.location.y as usize * self.grid.width + self.location.x as usize]
     }
 
     pub fn location(&self) -> Location {
         self.location
     }
 
-    // TODO: these should probably take a Heading
-    pub fn offset(&self, dx: Index, dy: Index) -> Option<Cell<'a, T>> {
-        self.grid.cell(self.location + vec2(dx, dy))
+    pub fn offset(&self, dx: Index, dy: Index) -> Option<Cell<'a, T, Tp>> {
+        self.step(vec2(dx, dy))
+    }
+
+    /// The adjacent cell in the given heading, or None if that is off the grid.
+    pub fn step(&self, heading: Heading) -> Option<Cell<'a, T, Tp>> {
+        self.grid.cell(self.location + heading)
     }
 
-    pub fn neighbors(&self) -> impl Iterator<Item = Cell<'a, T>> {
+    /// The cell `n` steps away in the given heading, or None if that is off the grid.
+    pub fn look(&self, heading: Heading, n: Index) -> Option<Cell<'a, T, Tp>> {
+        self.grid.cell(self.location + heading * n)
+    }
+
+    pub fn neighbors(&self) -> impl Iterator<Item = Cell<'a, T, Tp>> {
         let grid = self.grid;
         neighbors(self.location).flat_map(move |location| grid.cell(location))
     }
 
-    pub fn cardinal_neighbors(&self) -> impl Iterator<Item = Cell<'a, T>> {
+    pub fn cardinal_neighbors(&self) -> impl Iterator<Item = Cell<'a, T, Tp>> {
         let grid = self.grid;
         cardinal_neighbors(self.location).flat_map(move |location| grid.cell(location))
     }
 
-    /// Walks in the given direction until it hits the edge of the grid.
+    pub fn walk(&self, dx: Index, dy: Index) -> impl Iterator<Item = Cell<'a, T, Tp>> {
+        self.walk_toward(vec2(dx, dy))
+    }
+
+    /// Walks in the given direction until it hits the edge of the grid, or on a
+    /// toroidal grid, until it would come back around to this cell.
     /// This cell is not included in the iterator.
-    pub fn walk(&self, dx: Index, dy: Index) -> impl Iterator<Item = Cell<'a, T>> {
+    pub fn walk_toward(&self, heading: Heading) -> impl Iterator<Item = Cell<'a, T, Tp>> {
+        self.walk_with_step(heading, 1)
+    }
+
+    /// Like `walk_toward`, but only visits every `stride`th cell along the heading.
+    pub fn walk_with_step(
+        &self,
+        heading: Heading,
+        stride: Index,
+    ) -> impl Iterator<Item = Cell<'a, T, Tp>> {
+        let start = self.location;
+        let step = heading * stride;
         let mut cell = *self;
         std::iter::from_fn(move || {
-            if let Some(next) = cell.offset(dx, dy) {
-                cell = next;
-                Some(cell)
-            } else {
-                None
+            let next = cell.step(step)?;
+            if next.location == start {
+                return None;
             }
+            cell = next;
+            Some(cell)
         })
     }
 
-    /// Walks in the given direction until it hits the edge of the grid.
+    /// Walks in the given direction, stopping before the first cell matching the predicate
+    /// (e.g. a wall) or at the edge of the grid. This cell is not included in the iterator.
+    pub fn ray_until(
+        &self,
+        heading: Heading,
+        mut predicate: impl FnMut(&Cell<'a, T, Tp>) -> bool,
+    ) -> impl Iterator<Item = Cell<'a, T, Tp>> {
+        self.walk_toward(heading)
+            .take_while(move |cell| !predicate(cell))
+    }
+
+    /// Walks in the given direction until it hits the edge of the grid (or wraps back around).
     /// This cell is included in the iterator.
-    pub fn walk_inclusive(&self, dx: Index, dy: Index) -> impl Iterator<Item = Cell<'a, T>> {
-        std::iter::once(*self).chain(self.walk(dx, dy))
+    pub fn walk_inclusive(&self, dx: Index, dy: Index) -> impl Iterator<Item = Cell<'a, T, Tp>> {
+        std::iter::once(*self).chain(self.walk_toward(vec2(dx, dy)))
     }
 
-    pub fn manhattan_distance<'b>(&self, cheat_end: &Cell<'b, char>) -> usize {
-        (self.location.x - cheat_end.location.x).abs() as usize
-            + (self.location.y - cheat_end.location.y).abs() as usize
+    pub fn manhattan_distance<U, Up: Topology>(&self, other: &Cell<U, Up>) -> usize {
+        manhattan_distance(self.location, other.location)
     }
 }
 
-impl<'a, T> Clone for Cell<'a, T> {
+impl<'a, T, Tp: Topology> Clone for Cell<'a, T, Tp> {
     fn clone(&self) -> Self {
         Self {
             grid: self.grid,
@@ -225,77 +640,238 @@ impl<'a, T> Clone for Cell<'a, T> {
     }
 }
 
-impl<'a, T> Copy for Cell<'a, T> {}
+impl<'a, T, Tp: Topology> Copy for Cell<'a, T, Tp> {}
 
-impl<'a, T> PartialEq for Cell<'a, T> {
+impl<'a, T, Tp: Topology> PartialEq for Cell<'a, T, Tp> {
     fn eq(&self, other: &Self) -> bool {
         self.location == other.location && std::ptr::eq(self.grid, other.grid)
     }
 }
 
-impl<'a, T> Eq for Cell<'a, T> {}
+impl<'a, T, Tp: Topology> Eq for Cell<'a, T, Tp> {}
 
-impl<'a, T> Ord for Cell<'a, T> {
+/// Cells are ordered row-major, like `Grid::cells`.
+impl<'a, T, Tp: Topology> Ord for Cell<'a, T, Tp> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.location
-            .x
-            .cmp(&other.location.x)
-            .then(self.location.y.cmp(&other.location.y))
+        row_major_order(&self.location, &other.location)
             .then((self.grid as *const _ as usize).cmp(&(other.grid as *const _ as usize)))
     }
 }
 
-impl<'a, T> PartialOrd for Cell<'a, T> {
+impl<'a, T, Tp: Topology> PartialOrd for Cell<'a, T, Tp> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl<'a, T> std::hash::Hash for Cell<'a, T> {
+impl<'a, T, Tp: Topology> std::hash::Hash for Cell<'a, T, Tp> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.location.hash(state);
         std::ptr::hash(self.grid, state);
     }
 }
 
+/// Which cells count as adjacent: only the cardinal neighbors, or the diagonals too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn headings(self) -> impl Iterator<Item = Heading> {
+        let directions: &'static [Direction] = match self {
+            Connectivity::Four => &Direction::CARDINAL,
+            Connectivity::Eight => &Direction::ALL,
+        };
+        directions.iter().map(|d| d.heading())
+    }
+
+    pub fn neighbors(self, l: Location) -> impl Iterator<Item = Location> {
+        self.headings().map(move |heading| l + heading)
+    }
+}
+
+/// A set of locations on a grid, with queries about its shape.
 #[derive(Debug, Clone)]
 pub struct Region {
-    locations: HashSet<Location>,
+    /// The location of the first bit of `locations`, which only needs to cover the part of the
+    /// grid that the region can occupy.
+    origin: Location,
+    locations: LocationSet,
 }
 
 impl Region {
-    pub fn new() -> Self {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            origin: Location::zero(),
+            locations: LocationSet::new(width, height),
+        }
+    }
+
+    /// An empty region which can only hold locations within `bounds` (`max` is exclusive), and
+    /// only needs storage for those.
+    pub fn within(bounds: Box2D<Index>) -> Self {
+        let size = bounds.size();
         Self {
-            locations: HashSet::new(),
+            origin: bounds.min,
+            locations: LocationSet::new(size.width as usize, size.height as usize),
         }
     }
 
+    /// The region of all cells reachable from `start` by moving between adjacent cells
+    /// whose contents are `same`.
+    pub fn flood_fill<T, Tp: Topology>(
+        grid: &Grid<T, Tp>,
+        start: Location,
+        connectivity: Connectivity,
+        mut same: impl FnMut(&T, &T) -> bool,
+    ) -> Self {
+        let start = grid.cell(start).expect("Start should be on the grid");
+        let mut region = Region::new(grid.width(), grid.height());
+        region.insert(start.location());
+        let mut queue = vec![start];
+        while let Some(cell) = queue.pop() {
+            for next in connectivity
+                .neighbors(cell.location())
+                .filter_map(|l| grid.cell(l))
+            {
+                if !region.contains(next.location()) && same(cell.contents(), next.contents()) {
+                    region.insert(next.location());
+                    queue.push(next);
+                }
+            }
+        }
+        region
+    }
+
     pub fn insert(&mut self, location: Location) {
-        self.locations.insert(location);
+        self.locations.insert(location - self.origin.to_vector());
     }
 
     pub fn contains(&self, location: Location) -> bool {
-        self.locations.contains(&location)
+        self.locations.contains(location - self.origin.to_vector())
     }
 
+    /// Locations in row-major order.
     pub fn iter(&self) -> impl Iterator<Item = Location> + '_ {
-        self.locations.iter().copied()
-    }
-
-    pub fn neighbors(&self) -> impl Iterator<Item = Location> + '_ {
         self.locations
             .iter()
-            .flat_map(|location| neighbors(*location))
-            .filter(move |location| !self.locations.contains(location))
-            .collect::<HashSet<_>>() // to make unique
-            .into_iter()
+            .map(|location| location + self.origin.to_vector())
+    }
+
+    /// Locations outside the region which are adjacent to it. These may be off the grid.
+    pub fn neighbors(&self, connectivity: Connectivity) -> impl Iterator<Item = Location> + '_ {
+        self.iter()
+            .flat_map(move |location| connectivity.neighbors(location))
+            .filter(move |location| !self.contains(*location))
+            .unique()
     }
 
     pub fn len(&self) -> usize {
-        self.locations.len()
+        self.locations.count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    pub fn area(&self) -> usize {
+        self.len()
+    }
+
+    /// Number of cell edges between the region and the outside (including any holes).
+    pub fn perimeter(&self) -> usize {
+        self.iter()
+            .map(|l| cardinal_neighbors(l).filter(|n| !self.contains(*n)).count())
+            .sum()
+    }
+
+    /// Number of corners of the region's outline (including any holes), both convex and concave.
+    pub fn corners(&self) -> usize {
+        self.iter()
+            .map(|l| {
+                [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+                    .into_iter()
+                    .filter(|&(dx, dy)| {
+                        let horizontal = self.contains(l + vec2(dx, 0));
+                        let vertical = self.contains(l + vec2(0, dy));
+                        let diagonal = self.contains(l + vec2(dx, dy));
+                        (!horizontal && !vertical) || (horizontal && vertical && !diagonal)
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Number of straight sides of the region's outline (including any holes).
+    /// Every side ends in a corner, so this is the same as the number of corners.
+    pub fn sides(&self) -> usize {
+        self.corners()
+    }
+
+    /// The smallest box containing the region (`max` is exclusive), or None if it is empty.
+    pub fn bounding_box(&self) -> Option<Box2D<Index>> {
+        let (min, max) = self.iter().fold(None, |acc, l| match acc {
+            None => Some((l, l)),
+            Some((min, max)) => Some((min.min(l), max.max(l))),
+        })?;
+        Some(Box2D::new(min, max + vec2(1, 1)))
+    }
+
+    /// Cells of the region with at least one cardinal neighbor outside it.
+    pub fn boundary_cells(&self) -> impl Iterator<Item = Location> + '_ {
+        self.iter()
+            .filter(|l| cardinal_neighbors(*l).any(|n| !self.contains(n)))
+    }
+
+    /// Groups of cardinally-connected cells which are not in the region but are enclosed by it.
+    pub fn holes(&self) -> Vec<Region> {
+        let Some(bounds) = self.bounding_box() else {
+            return vec![];
+        };
+        // leave a one-cell margin so everything outside the region is connected
+        let origin = bounds.min - vec2(1, 1);
+        let size = bounds.size() + Size2D::new(2, 2);
+        let local = Grid::new(false, size.width as usize, size.height as usize)
+            .map(|c| self.contains(c.location() + origin.to_vector()));
+        let outside =
+            Region::flood_fill(&local, Location::new(0, 0), Connectivity::Four, |a, b| {
+                a == b
+            });
+
+        let mut holes: Vec<Region> = vec![];
+        for cell in local.cells() {
+            let location = cell.location();
+            if *cell.contents()
+                || outside.contains(location)
+                || holes
+                    .iter()
+                    .any(|h| h.contains(location + origin.to_vector()))
+            {
+                continue;
+            }
+            let local_hole =
+                Region::flood_fill(&local, location, Connectivity::Four, |a, b| a == b);
+            let mut hole = Region::within(bounds);
+            for l in local_hole.iter() {
+                hole.insert(l + origin.to_vector());
+            }
+            holes.push(hole);
+        }
+        holes
     }
 }
 
+/// Regions are equal if they contain the same locations, however they are stored.
+impl PartialEq for Region {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|location| other.contains(location))
+    }
+}
+
+impl Eq for Region {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Face {
     // true == face runs north-south
@@ -371,8 +947,124 @@ impl Face {
 #[cfg(test)]
 mod test {
 
+    use std::collections::HashSet;
+
     use super::*;
 
+    #[test]
+    fn test_toroidal_wrapping() {
+        let grid = Grid::new_with_lines(["abc", "def"].iter()).with_topology::<Toroidal>();
+        let corner = grid.cell(Location::new(0, 0)).unwrap();
+        assert_eq!(*corner.offset(-1, -1).unwrap().contents(), 'f');
+        assert_eq!(corner.cardinal_neighbors().count(), 4);
+        assert_eq!(*grid.cell(Location::new(-5, 5)).unwrap().contents(), 'e');
+
+        let row: String = corner.walk_inclusive(1, 0).map(|c| *c.contents()).collect();
+        assert_eq!(row, "abc");
+        let diagonal: String = corner.walk(1, 1).map(|c| *c.contents()).collect();
+        assert_eq!(diagonal, "ecdbf");
+    }
+
+    #[test]
+    fn test_display() {
+        let grid = Grid::new_with_lines(["ab", "cd"].iter());
+        assert_eq!(grid.to_string(), "ab\ncd\n");
+
+        let numbers = grid.map(|c| (c.location().x + 10 * c.location().y) * 5);
+        assert_eq!(numbers.to_string(), " 0  5\n50 55\n");
+
+        let rendered = numbers
+            .render(|c| if *c.contents() > 10 { '#' } else { '.' })
+            .overlay([Location::new(0, 0), Location::new(2, 2)], '*');
+        assert_eq!(rendered.to_string(), "*.\n##\n");
+    }
+
+    #[test]
+    fn test_heading_walks() {
+        let grid = Grid::new_with_lines(["abcde", "fg#ij"].iter());
+        let start = grid.cell(Location::new(0, 1)).unwrap();
+        assert_eq!(*start.step(NORTH).unwrap().contents(), 'a');
+        assert!(start.step(WEST).is_none());
+        assert_eq!(*start.look(EAST, 4).unwrap().contents(), 'j');
+
+        let contents = |cells: Vec<Cell<char>>| -> String {
+            cells.into_iter().map(|c| *c.contents()).collect()
+        };
+        assert_eq!(contents(start.walk_toward(EAST).collect()), "g#ij");
+        assert_eq!(contents(start.walk_with_step(EAST, 2).collect()), "#j");
+        let until_wall = start.ray_until(EAST, |c| *c.contents() == '#');
+        assert_eq!(contents(until_wall.collect()), "g");
+    }
+
+    #[test]
+    fn test_location_api() {
+        let mut grid = Grid::new_with_lines(["a#c", "def"].iter());
+        assert_eq!(grid.position(|&c| c == 'c'), Some(Location::new(2, 0)));
+        assert_eq!(grid.get(Location::new(3, 0)), None);
+        grid[Location::new(1, 1)] = '#';
+        assert_eq!(
+            grid.passable_neighbors(Location::new(0, 0), |&c| c != '#')
+                .collect_vec(),
+            vec![Location::new(0, 1)]
+        );
+
+        let cells = grid.cells().collect_vec();
+        assert!(cells.iter().tuple_windows().all(|(a, b)| a < b));
+        assert_eq!(
+            manhattan_distance(Location::new(0, 1), Location::new(2, 0)),
+            3
+        );
+    }
+
+    #[test]
+    fn test_directions() {
+        for direction in Direction::ALL {
+            assert_eq!(Direction::from_index(direction.index()), direction);
+            assert_eq!(Direction::try_from(direction.heading()), Ok(direction));
+            assert_eq!(direction.reverse().heading(), -direction.heading());
+            assert_eq!(direction.turn_right().turn_left(), direction);
+        }
+        for direction in Direction::CARDINAL {
+            assert!(direction.is_cardinal());
+            assert_eq!(Direction::from_char(direction.arrow()), Some(direction));
+            assert_eq!(
+                Direction::from_char(direction.udlr().unwrap()),
+                Some(direction)
+            );
+        }
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::NorthWest.rotate(1), Direction::North);
+        assert_eq!(Direction::try_from(vec2(2, 0)), Err(vec2(2, 0)));
+    }
+
+    #[test]
+    fn test_region_geometry() {
+        let grid = Grid::new_with_lines(
+            ["AAAAAA", "AAABBA", "AAABBA", "ABBAAA", "ABBAAA", "AAAAAA"].iter(),
+        );
+        let a = Region::flood_fill(&grid, Location::new(0, 0), Connectivity::Four, |a, b| {
+            a == b
+        });
+        assert_eq!(a.area(), 28);
+        assert_eq!(a.perimeter(), 40);
+        assert_eq!(a.sides(), 12);
+        assert_eq!(
+            a.bounding_box(),
+            Some(Box2D::new(point2(0, 0), point2(6, 6)))
+        );
+        assert_eq!(a.boundary_cells().count(), 26);
+        assert_eq!(a.neighbors(Connectivity::Four).count(), 4 * 6 + 8);
+        let holes = a.holes();
+        assert_eq!(holes.len(), 2);
+        assert!(holes.iter().all(|h| h.area() == 4 && h.sides() == 4));
+
+        let b = Region::flood_fill(&grid, Location::new(1, 3), Connectivity::Four, |a, b| {
+            a == b
+        });
+        assert_eq!(b.iter().collect_vec(), holes[1].iter().collect_vec());
+        assert!(b.holes().is_empty());
+    }
+
     #[test]
     fn test_face_normalization() {
         let location = Location::new(0, 0);
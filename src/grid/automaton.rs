@@ -0,0 +1,214 @@
+use std::{
+    hash::{BuildHasher, Hash, RandomState},
+    marker::PhantomData,
+};
+
+use crate::prelude::*;
+
+impl<T, Tp: Topology> Grid<T, Tp> {
+    /// Like `map`, but computes rows in parallel.
+    pub fn par_map<U: Send>(&self, f: impl Fn(Cell<T, Tp>) -> U + Sync) -> Grid<U, Tp>
+    where
+        T: Sync,
+    {
+        let width = self.width;
+        Grid {
+            contents: (0..self.height)
+                .into_par_iter()
+                .flat_map_iter(|y| {
+                    let f = &f;
+                    (0..width).map(move |x| {
+                        f(Cell {
+                            grid: self,
+                            location: Location::new(x as Index, y as Index),
+                        })
+                    })
+                })
+                .collect(),
+            width,
+            height: self.height,
+            topology: PhantomData,
+        }
+    }
+
+    /// Like `map_into`, but computes rows in parallel. `target` must already be the same size
+    /// as this grid, since its storage is overwritten in place.
+    pub fn par_map_into<U: Send>(
+        &self,
+        target: &mut Grid<U, Tp>,
+        f: impl Fn(Cell<T, Tp>) -> U + Sync,
+    ) where
+        T: Sync,
+    {
+        assert_eq!((self.width, self.height), (target.width, target.height));
+        target
+            .contents
+            .par_chunks_mut(self.width.max(1))
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, contents) in row.iter_mut().enumerate() {
+                    *contents = f(Cell {
+                        grid: self,
+                        location: Location::new(x as Index, y as Index),
+                    });
+                }
+            });
+    }
+
+    /// One generation of a cellular automaton: every cell's next contents, computed from the
+    /// cell and its neighborhood in this generation.
+    pub fn step_automaton(&self, rule: impl FnMut(Cell<T, Tp>) -> T) -> Grid<T, Tp> {
+        self.map(rule)
+    }
+
+    /// Like `step_automaton`, but computes rows in parallel.
+    pub fn par_step_automaton(&self, rule: impl Fn(Cell<T, Tp>) -> T + Sync) -> Grid<T, Tp>
+    where
+        T: Send + Sync,
+    {
+        self.par_map(rule)
+    }
+}
+
+/// A cycle found while running an `Automaton`: the state at `start + period` is the state at
+/// `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutomatonCycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+/// Runs a cellular automaton over many generations, alternating between two buffers
+/// rather than allocating a new grid every generation.
+#[derive(Debug, Clone)]
+pub struct Automaton<T, Tp = Bounded> {
+    grid: Grid<T, Tp>,
+    spare: Grid<T, Tp>,
+    generation: usize,
+}
+
+impl<T: Clone, Tp: Topology> Automaton<T, Tp> {
+    pub fn new(grid: Grid<T, Tp>) -> Self {
+        Self {
+            spare: grid.clone(),
+            grid,
+            generation: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<T, Tp> {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid<T, Tp> {
+        self.grid
+    }
+
+    /// The number of generations run so far.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn step(&mut self, rule: impl FnMut(Cell<T, Tp>) -> T) {
+        self.grid.map_into(&mut self.spare, rule);
+        std::mem::swap(&mut self.grid, &mut self.spare);
+        self.generation += 1;
+    }
+
+    /// Like `step`, but computes rows in parallel.
+    pub fn par_step(&mut self, rule: impl Fn(Cell<T, Tp>) -> T + Sync)
+    where
+        T: Send + Sync,
+    {
+        self.grid.par_map_into(&mut self.spare, rule);
+        std::mem::swap(&mut self.grid, &mut self.spare);
+        self.generation += 1;
+    }
+
+    pub fn run(&mut self, generations: usize, mut rule: impl FnMut(Cell<T, Tp>) -> T) {
+        for _ in 0..generations {
+            self.step(&mut rule);
+        }
+    }
+
+    /// Runs the given number of generations, but once a state repeats, skips ahead by whole
+    /// cycles. Returns the cycle, if one was found.
+    pub fn run_detecting_cycles(
+        &mut self,
+        generations: usize,
+        mut rule: impl FnMut(Cell<T, Tp>) -> T,
+    ) -> Option<AutomatonCycle>
+    where
+        T: Hash + Eq,
+    {
+        let hasher = RandomState::new();
+        // generations by the hash of their state. A matching hash is confirmed by replaying
+        // from the initial state, so only that one grid has to be kept.
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let initial = self.grid.clone();
+        let first = self.generation;
+        let end = first + generations;
+
+        while self.generation < end {
+            let hash = hasher.hash_one(&self.grid);
+            let candidates = seen.entry(hash).or_default();
+            for &start in candidates.iter() {
+                let mut replay = Automaton::new(initial.clone());
+                replay.run(start - first, &mut rule);
+                if replay.grid == self.grid {
+                    // this state is the state at `start`, so whole cycles can be skipped
+                    let period = self.generation - start;
+                    self.run((end - self.generation) % period, &mut rule);
+                    self.generation = end;
+                    return Some(AutomatonCycle { start, period });
+                }
+            }
+            candidates.push(self.generation);
+            self.step(&mut rule);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn life(cell: Cell<char, Toroidal>) -> char {
+        let alive = cell.neighbors().filter(|n| *n.contents() == '#').count();
+        match (*cell.contents(), alive) {
+            ('#', 2 | 3) | ('.', 3) => '#',
+            _ => '.',
+        }
+    }
+
+    #[test]
+    fn test_automaton() {
+        let glider = Grid::new_with_lines([".#...", "..#..", "###..", ".....", "....."].iter())
+            .with_topology::<Toroidal>();
+
+        let mut simple = Automaton::new(glider.clone());
+        simple.run(23, life);
+        assert_eq!(glider.par_step_automaton(life), glider.step_automaton(life));
+        let mut parallel = Automaton::new(glider.clone());
+        for _ in 0..23 {
+            parallel.par_step(life);
+        }
+        assert_eq!(parallel.grid(), simple.grid());
+
+        // a glider on a 5x5 torus comes back to where it started after 20 generations
+        let mut skipping = Automaton::new(glider.clone());
+        let cycle = skipping.run_detecting_cycles(1_000_000_003, life);
+        assert_eq!(
+            cycle,
+            Some(AutomatonCycle {
+                start: 0,
+                period: 20
+            })
+        );
+        assert_eq!(skipping.generation(), 1_000_000_003);
+        assert_eq!(skipping.grid(), simple.grid());
+        assert_ne!(skipping.grid(), &glider);
+    }
+}
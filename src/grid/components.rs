@@ -0,0 +1,106 @@
+use crate::prelude::*;
+
+use graph::UnionFind;
+
+#[derive(Debug, Clone)]
+pub struct ComponentInfo {
+    pub label: usize,
+    /// The first cell of the component in row-major order.
+    pub start: Location,
+    pub size: usize,
+    pub region: Region,
+}
+
+impl<T> Grid<T> {
+    /// Labels each group of connected cells whose contents are `same`, numbering them from 0
+    /// in row-major order of their first cell.
+    pub fn label_components(
+        &self,
+        connectivity: Connectivity,
+        mut same: impl FnMut(&T, &T) -> bool,
+    ) -> (Grid<usize>, Vec<ComponentInfo>) {
+        let mut sets = UnionFind::new(self.width * self.height);
+        for cell in self.cells() {
+            let index = self.index(cell.location());
+            // each pair is considered once, from whichever cell comes later
+            for neighbor in connectivity
+                .neighbors(cell.location())
+                .filter_map(|l| self.cell(l))
+            {
+                let neighbor_index = self.index(neighbor.location());
+                if neighbor_index < index && same(cell.contents(), neighbor.contents()) {
+                    sets.union(index, neighbor_index);
+                }
+            }
+        }
+
+        let mut label_for_root = vec![None; self.width * self.height];
+        // the first cell, size and (inclusive) bounds of each component, by label
+        let mut found: Vec<(Location, usize, Box2D<Index>)> = vec![];
+        let labels = self.map(|cell| {
+            let location = cell.location();
+            let root = sets.find(self.index(location));
+            let label = *label_for_root[root].get_or_insert_with(|| {
+                found.push((location, sets.size(root), Box2D::new(location, location)));
+                found.len() - 1
+            });
+            let bounds = &mut found[label].2;
+            bounds.min = bounds.min.min(location);
+            bounds.max = bounds.max.max(location);
+            label
+        });
+
+        // each region only stores its bounding box, rather than the whole grid
+        let mut components = found
+            .into_iter()
+            .enumerate()
+            .map(|(label, (start, size, bounds))| ComponentInfo {
+                label,
+                start,
+                size,
+                region: Region::within(Box2D::new(bounds.min, bounds.max + vec2(1, 1))),
+            })
+            .collect_vec();
+        for cell in labels.cells() {
+            components[*cell.contents()].region.insert(cell.location());
+        }
+
+        (labels, components)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_label_components() {
+        let grid = Grid::new_with_lines(["AAB", "BAB", "BBA"].iter());
+
+        let (labels, components) = grid.label_components(Connectivity::Four, |a, b| a == b);
+        assert_eq!(labels.to_string(), "001\n201\n223\n");
+        assert_eq!(
+            components.iter().map(|c| c.size).collect_vec(),
+            vec![3, 2, 3, 1]
+        );
+        assert_eq!(components[2].start, Location::new(0, 1));
+        assert!(components[3].region.contains(Location::new(2, 2)));
+        assert_eq!(
+            components[2].region.iter().collect_vec(),
+            vec![
+                Location::new(0, 1),
+                Location::new(0, 2),
+                Location::new(1, 2)
+            ]
+        );
+        assert_eq!(
+            components[2].region,
+            Region::flood_fill(&grid, Location::new(0, 1), Connectivity::Four, |a, b| a
+                == b)
+        );
+
+        let (labels, components) = grid.label_components(Connectivity::Eight, |a, b| a == b);
+        assert_eq!(labels.to_string(), "001\n101\n110\n");
+        assert_eq!(components.len(), 2);
+    }
+}
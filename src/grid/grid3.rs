@@ -0,0 +1,176 @@
+use crate::prelude::*;
+
+pub type Location3 = euclid::default::Point3D<Index>;
+pub type Heading3 = euclid::default::Vector3D<Index>;
+
+/// Which cells count as adjacent in 3D: those sharing a face, or also those sharing an edge
+/// or corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity3 {
+    Six,
+    TwentySix,
+}
+
+impl Connectivity3 {
+    pub fn headings(self) -> impl Iterator<Item = Heading3> {
+        (-1..=1)
+            .flat_map(|dz| {
+                (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| Heading3::new(dx, dy, dz)))
+            })
+            .filter(move |h| {
+                let nonzero = [h.x, h.y, h.z].iter().filter(|&&d| d != 0).count();
+                match self {
+                    Connectivity3::Six => nonzero == 1,
+                    Connectivity3::TwentySix => nonzero > 0,
+                }
+            })
+    }
+
+    pub fn neighbors(self, l: Location3) -> impl Iterator<Item = Location3> {
+        self.headings().map(move |heading| l + heading)
+    }
+}
+
+/// A dense 3D grid, stored as layers of rows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid3<T> {
+    contents: Vec<T>,
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl<T> Grid3<T> {
+    pub fn new(element: T, width: usize, height: usize, depth: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            contents: vec![element; width * height * depth],
+            width,
+            height,
+            depth,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn index(&self, location: Location3) -> Option<usize> {
+        if location.x < 0
+            || location.y < 0
+            || location.z < 0
+            || location.x >= self.width as Index
+            || location.y >= self.height as Index
+            || location.z >= self.depth as Index
+        {
+            None
+        } else {
+            Some(
+                (location.z as usize * self.height + location.y as usize) * self.width
+                    + location.x as usize,
+            )
+        }
+    }
+
+    pub fn get(&self, location: Location3) -> Option<&T> {
+        self.index(location).map(|index| &self.contents[index])
+    }
+
+    pub fn set(&mut self, location: Location3, value: T) {
+        let index = self.index(location).expect("Location should be in bounds");
+        self.contents[index] = value;
+    }
+
+    /// All locations, layer by layer, each in row-major order.
+    pub fn locations(&self) -> impl Iterator<Item = Location3> + Clone {
+        let (width, height, depth) = (
+            self.width as Index,
+            self.height as Index,
+            self.depth as Index,
+        );
+        (0..depth).flat_map(move |z| {
+            (0..height).flat_map(move |y| (0..width).map(move |x| Location3::new(x, y, z)))
+        })
+    }
+
+    /// Adjacent locations which are in bounds.
+    pub fn neighbors(
+        &self,
+        location: Location3,
+        connectivity: Connectivity3,
+    ) -> impl Iterator<Item = Location3> + '_ {
+        connectivity
+            .neighbors(location)
+            .filter(|&l| self.index(l).is_some())
+    }
+}
+
+impl Grid3<char> {
+    /// Parses layers of lines, with layers separated by blank lines.
+    pub fn new_with_layers(input: &str) -> Self {
+        let layers = input
+            .split("\n\n")
+            .map(|layer| Grid::new_with_lines(layer.lines()))
+            .collect_vec();
+        let (width, height) = (layers[0].width(), layers[0].height());
+        assert!(layers
+            .iter()
+            .all(|layer| layer.width() == width && layer.height() == height));
+
+        let mut result = Grid3::new(' ', width, height, layers.len());
+        for (z, layer) in layers.iter().enumerate() {
+            for cell in layer.cells() {
+                let l = cell.location();
+                result.set(Location3::new(l.x, l.y, z as Index), *cell.contents());
+            }
+        }
+        result
+    }
+}
+
+/// 3D grids are connected through faces.
+impl<T> Lattice for Grid3<T> {
+    type Location = Location3;
+    type Item = T;
+
+    fn get(&self, location: Location3) -> Option<&T> {
+        Grid3::get(self, location)
+    }
+
+    fn all_locations(&self) -> impl Iterator<Item = Location3> + '_ {
+        self.locations()
+    }
+
+    fn adjacent(&self, location: Location3) -> impl Iterator<Item = Location3> + '_ {
+        self.neighbors(location, Connectivity3::Six)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grid3() {
+        let grid = Grid3::new_with_layers("#.\n..\n\n..\n.#");
+        assert_eq!((grid.width(), grid.height(), grid.depth()), (2, 2, 2));
+        let corner = Location3::new(0, 0, 0);
+        assert_eq!(grid.neighbors(corner, Connectivity3::Six).count(), 3);
+        assert_eq!(grid.neighbors(corner, Connectivity3::TwentySix).count(), 7);
+        assert_eq!(Connectivity3::TwentySix.headings().count(), 26);
+
+        let distances = grid.distances_from([Location3::new(1, 0, 0)], |&c| c != '#');
+        assert_eq!(distances.get(&Location3::new(0, 1, 1)), Some(&3));
+        assert_eq!(grid.regions(|a, b| a == b).len(), 3);
+    }
+}
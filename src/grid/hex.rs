@@ -0,0 +1,208 @@
+use std::fmt::Display;
+
+use crate::prelude::*;
+
+pub struct HexUnit;
+
+/// Axial hex coordinates (q, r) for pointy-topped hexes: q increases to the east and
+/// r increases to the south-east.
+pub type HexLocation = euclid::Point2D<Index, HexUnit>;
+pub type HexHeading = euclid::Vector2D<Index, HexUnit>;
+
+/// The six hex directions, clockwise from east.
+pub const HEX_HEADINGS: [HexHeading; 6] = [
+    HexHeading::new(1, 0),
+    HexHeading::new(0, 1),
+    HexHeading::new(-1, 1),
+    HexHeading::new(-1, 0),
+    HexHeading::new(0, -1),
+    HexHeading::new(1, -1),
+];
+
+pub fn hex_neighbors(l: HexLocation) -> impl Iterator<Item = HexLocation> {
+    HEX_HEADINGS.iter().map(move |&heading| l + heading)
+}
+
+pub fn hex_distance(a: HexLocation, b: HexLocation) -> usize {
+    let delta = a - b;
+    (delta.x.unsigned_abs() + delta.y.unsigned_abs() + (delta.x + delta.y).unsigned_abs()) / 2
+}
+
+/// A hex grid of any shape. In text, rows are staggered by one character and cells are
+/// separated by spaces, so each row's cells line up between those of the rows around it:
+///
+/// ```text
+///  a b
+/// c d e
+///  f g
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexGrid<T> {
+    contents: HashMap<HexLocation, T>,
+    // in row-major order
+    locations: Vec<HexLocation>,
+}
+
+impl<T> HexGrid<T> {
+    pub fn from_cells(cells: impl IntoIterator<Item = (HexLocation, T)>) -> Self {
+        let mut result = Self {
+            contents: HashMap::new(),
+            locations: vec![],
+        };
+        for (location, value) in cells {
+            result.insert(location, value);
+        }
+        result
+    }
+
+    /// Sets the contents of the location, adding it to the grid if needed.
+    pub fn insert(&mut self, location: HexLocation, value: T) {
+        if self.contents.insert(location, value).is_none() {
+            let index = self
+                .locations
+                .partition_point(|l| (l.y, l.x + l.y) < (location.y, location.x + location.y));
+            self.locations.insert(index, location);
+        }
+    }
+
+    pub fn get(&self, location: HexLocation) -> Option<&T> {
+        self.contents.get(&location)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Locations in row-major order.
+    pub fn locations(&self) -> impl Iterator<Item = HexLocation> + '_ {
+        self.locations.iter().copied()
+    }
+
+    /// Adjacent locations which are on the grid.
+    pub fn neighbors(&self, location: HexLocation) -> impl Iterator<Item = HexLocation> + '_ {
+        hex_neighbors(location).filter(|l| self.contents.contains_key(l))
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(HexLocation, &T) -> U) -> HexGrid<U> {
+        HexGrid {
+            contents: self
+                .locations()
+                .map(|l| (l, f(l, &self.contents[&l])))
+                .collect(),
+            locations: self.locations.clone(),
+        }
+    }
+}
+
+impl HexGrid<char> {
+    /// Parses the staggered text layout described on `HexGrid`. The first cell of the first
+    /// non-empty row is at q = 0 within its row.
+    pub fn new_with_lines(lines: impl Iterator<Item = impl AsRef<str>>) -> Self {
+        let mut cells = vec![];
+        // parity of column + row shared by every cell
+        let mut parity = None;
+        for (r, line) in lines.enumerate() {
+            for (column, c) in line.as_ref().chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                // columns are "doubled" coordinates: each step east is two characters
+                let doubled = column as Index - r as Index;
+                let parity = *parity.get_or_insert(doubled.rem_euclid(2));
+                assert!(
+                    doubled.rem_euclid(2) == parity,
+                    "Hex cell at row {}, column {} is not aligned with the first row",
+                    r,
+                    column
+                );
+                cells.push((HexLocation::new((doubled - parity) / 2, r as Index), c));
+            }
+        }
+        Self::from_cells(cells)
+    }
+}
+
+/// Cells are right-aligned to the widest cell, and each step between doubled columns is that
+/// wide, so a `HexGrid<char>` prints exactly as its input lines.
+impl<T: Display> Display for HexGrid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let column = |l: &HexLocation| 2 * l.x + l.y;
+        let Some(min_column) = self.locations.iter().map(column).min() else {
+            return Ok(());
+        };
+        let rendered: HashMap<HexLocation, String> = self
+            .contents
+            .iter()
+            .map(|(&location, contents)| (location, contents.to_string()))
+            .collect();
+        let column_width = rendered
+            .values()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or_default();
+        for (_, row) in &self.locations.iter().chunk_by(|l| l.y) {
+            let mut line = String::new();
+            for location in row {
+                // cells in a row are at least two doubled columns apart, so this never
+                // overlaps the previous cell
+                let end = (column(location) - min_column) as usize * column_width + column_width;
+                let padding = end - line.chars().count();
+                line.push_str(&format!("{:>padding$}", rendered[location]));
+            }
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Lattice for HexGrid<T> {
+    type Location = HexLocation;
+    type Item = T;
+
+    fn get(&self, location: HexLocation) -> Option<&T> {
+        HexGrid::get(self, location)
+    }
+
+    fn all_locations(&self) -> impl Iterator<Item = HexLocation> + '_ {
+        self.locations()
+    }
+
+    fn adjacent(&self, location: HexLocation) -> impl Iterator<Item = HexLocation> + '_ {
+        self.neighbors(location)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hex_grid() {
+        let text = " a b\nc # d\n e f\n";
+        let grid = HexGrid::new_with_lines(text.lines());
+        assert_eq!(grid.len(), 7);
+        assert_eq!(grid.to_string(), text);
+
+        let numbers = HexGrid::from_cells([
+            (HexLocation::new(0, 0), 100),
+            (HexLocation::new(1, 0), 7),
+            (HexLocation::new(0, 1), 42),
+        ]);
+        assert_eq!(numbers.to_string(), "100     7\n    42\n");
+
+        let center = grid
+            .locations()
+            .find(|&l| grid.get(l) == Some(&'#'))
+            .unwrap();
+        assert_eq!(grid.neighbors(center).count(), 6);
+        assert!(grid.neighbors(center).all(|n| hex_distance(center, n) == 1));
+
+        let distances = grid.distances_from([HexLocation::new(-1, 1)], |&c| c != '#');
+        assert_eq!(distances[&HexLocation::new(1, 1)], 3);
+        assert_eq!(grid.regions(|a, b| (*a == '#') == (*b == '#')).len(), 2);
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    hash::Hash,
+};
+
+use crate::prelude::*;
+
+/// Anything made of cells at discrete locations with a notion of adjacency: square, hex and
+/// 3D grids. Searches written against this work on all of them.
+pub trait Lattice {
+    type Location: Copy + Eq + Hash;
+    type Item;
+
+    fn get(&self, location: Self::Location) -> Option<&Self::Item>;
+
+    /// Every location, in a consistent order.
+    fn all_locations(&self) -> impl Iterator<Item = Self::Location> + '_;
+
+    /// Locations on the lattice which are adjacent to this one.
+    fn adjacent(&self, location: Self::Location) -> impl Iterator<Item = Self::Location> + '_;
+
+    /// Groups of adjacent locations whose contents are `same`, via `graph::flood_fill_from`.
+    fn regions(&self, same: impl Fn(&Self::Item, &Self::Item) -> bool) -> Vec<Vec<Self::Location>> {
+        graph::flood_fill_from(self.all_locations(), |&location| {
+            let contents = self.get(location).unwrap();
+            self.adjacent(location)
+                .filter(|&next| same(contents, self.get(next).unwrap()))
+                .collect_vec()
+                .into_iter()
+        })
+    }
+
+    /// Breadth-first distances from the nearest of `starts`, moving through passable cells.
+    fn distances_from(
+        &self,
+        starts: impl IntoIterator<Item = Self::Location>,
+        mut passable: impl FnMut(&Self::Item) -> bool,
+    ) -> HashMap<Self::Location, u32> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        for start in starts {
+            if let Entry::Vacant(entry) = distances.entry(start) {
+                entry.insert(0);
+                queue.push_back(start);
+            }
+        }
+
+        while let Some(location) = queue.pop_front() {
+            let distance = distances[&location];
+            for next in self.adjacent(location) {
+                if let Entry::Vacant(entry) = distances.entry(next) {
+                    if passable(self.get(next).unwrap()) {
+                        entry.insert(distance + 1);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        distances
+    }
+}
+
+/// Square grids are cardinally connected.
+impl<T, Tp: Topology> Lattice for Grid<T, Tp> {
+    type Location = Location;
+    type Item = T;
+
+    fn get(&self, location: Location) -> Option<&T> {
+        Grid::get(self, location)
+    }
+
+    fn all_locations(&self) -> impl Iterator<Item = Location> + '_ {
+        self.locations()
+    }
+
+    fn adjacent(&self, location: Location) -> impl Iterator<Item = Location> + '_ {
+        self.neighbors(location, Connectivity::Four)
+    }
+}
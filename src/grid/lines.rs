@@ -0,0 +1,239 @@
+use itertools::Either;
+use num::{integer::gcd, rational::Ratio};
+
+use crate::prelude::*;
+
+/// The cells a raster line from `a` to `b` passes through (Bresenham), including both ends.
+pub fn line(a: Location, b: Location) -> impl Iterator<Item = Location> {
+    let delta = b - a;
+    let (dx, dy) = (delta.x.abs(), -delta.y.abs());
+    let (sx, sy) = (delta.x.signum(), delta.y.signum());
+    let mut error = dx + dy;
+    let mut next = Some(a);
+    std::iter::from_fn(move || {
+        let location = next?;
+        next = if location == b {
+            None
+        } else {
+            let mut step = location;
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                step.x += sx;
+            }
+            if doubled <= dx {
+                error += dx;
+                step.y += sy;
+            }
+            Some(step)
+        };
+        Some(location)
+    })
+}
+
+/// The heading divided by the gcd of its components, and that gcd. The zero heading is its
+/// own primitive heading, with a multiple of zero.
+pub fn primitive_heading(heading: Heading) -> (Heading, Index) {
+    let divisor = gcd(heading.x, heading.y);
+    if divisor == 0 {
+        (heading, 0)
+    } else {
+        (heading / divisor, divisor)
+    }
+}
+
+/// The locations lying exactly on the segment from `a` to `b`, including both ends.
+pub fn lattice_points_on_line(a: Location, b: Location) -> impl Iterator<Item = Location> {
+    let (unit, count) = primitive_heading(b - a);
+    (0..=count).map(move |n| a + unit * n)
+}
+
+/// An unbounded ray of locations, starting at `location` and stepping by `heading`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ray {
+    location: Location,
+    heading: Heading,
+}
+
+pub fn ray(location: Location, heading: Heading) -> Ray {
+    Ray { location, heading }
+}
+
+impl Ray {
+    /// The cells along the ray (starting with its origin) until it leaves the grid, or on a
+    /// toroidal grid, until it comes back around to the origin.
+    pub fn take_while_in<T, Tp: Topology>(
+        self,
+        grid: &Grid<T, Tp>,
+    ) -> impl Iterator<Item = Cell<'_, T, Tp>> {
+        match grid.cell(self.location) {
+            Some(cell) => Either::Left(cell.walk_inclusive(self.heading.x, self.heading.y)),
+            None => Either::Right(std::iter::empty()),
+        }
+    }
+}
+
+impl Iterator for Ray {
+    type Item = Location;
+
+    fn next(&mut self) -> Option<Location> {
+        let location = self.location;
+        self.location += self.heading;
+        Some(location)
+    }
+}
+
+/// One row of a quadrant scan in `visible_from`: the columns at `depth` between two slopes.
+struct ScanRow {
+    depth: Index,
+    start_slope: Ratio<Index>,
+    end_slope: Ratio<Index>,
+}
+
+impl ScanRow {
+    fn columns(&self) -> std::ops::RangeInclusive<Index> {
+        let half = Ratio::new(1, 2);
+        let depth = Ratio::from_integer(self.depth);
+        let min = (depth * self.start_slope + half).floor().to_integer();
+        let max = (depth * self.end_slope - half).ceil().to_integer();
+        min..=max
+    }
+
+    fn is_symmetric(&self, column: Index) -> bool {
+        let column = Ratio::from_integer(column);
+        let depth = Ratio::from_integer(self.depth);
+        column >= depth * self.start_slope && column <= depth * self.end_slope
+    }
+
+    fn next(&self) -> ScanRow {
+        ScanRow {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+}
+
+fn slope(depth: Index, column: Index) -> Ratio<Index> {
+    Ratio::new(2 * column - 1, 2 * depth)
+}
+
+impl<T, Tp: Topology> Grid<T, Tp> {
+    /// The locations visible from `origin`, using symmetric shadowcasting: the first cell
+    /// that blocks sight in each direction is visible, but nothing behind it is. The origin
+    /// is always visible.
+    pub fn visible_from(
+        &self,
+        origin: Location,
+        mut blocker: impl FnMut(&T) -> bool,
+    ) -> LocationSet {
+        let mut visible = LocationSet::for_grid(self);
+        visible.insert(
+            self.normalize(origin)
+                .expect("Origin should be on the grid"),
+        );
+        // on toroidal grids, don't look further than once around
+        let max_depth = self.width().max(self.height()) as Index;
+
+        for (forward, across) in [(NORTH, EAST), (EAST, SOUTH), (SOUTH, WEST), (WEST, NORTH)] {
+            let to_location =
+                |depth: Index, column: Index| origin + forward * depth + across * column;
+            let mut rows = vec![ScanRow {
+                depth: 1,
+                start_slope: Ratio::from_integer(-1),
+                end_slope: Ratio::from_integer(1),
+            }];
+
+            while let Some(mut row) = rows.pop() {
+                if row.depth > max_depth {
+                    continue;
+                }
+                // whether the previous cell in this row blocked sight
+                let mut previous_blocked = None;
+                for column in row.columns() {
+                    let location = to_location(row.depth, column);
+                    let blocked = match self.get(location) {
+                        Some(contents) => {
+                            let blocked = blocker(contents);
+                            if blocked || row.is_symmetric(column) {
+                                visible.insert(self.normalize(location).unwrap());
+                            }
+                            blocked
+                        }
+                        // there's nothing to see past the edge of the grid
+                        None => true,
+                    };
+                    match (previous_blocked, blocked) {
+                        (Some(true), false) => row.start_slope = slope(row.depth, column),
+                        (Some(false), true) => rows.push(ScanRow {
+                            end_slope: slope(row.depth, column),
+                            ..row.next()
+                        }),
+                        _ => {}
+                    }
+                    previous_blocked = Some(blocked);
+                }
+                if previous_blocked == Some(false) {
+                    rows.push(row.next());
+                }
+            }
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        assert_eq!(
+            line(point2(0, 0), point2(4, 2)).collect_vec(),
+            vec![
+                point2(0, 0),
+                point2(1, 1),
+                point2(2, 1),
+                point2(3, 2),
+                point2(4, 2)
+            ]
+        );
+        assert_eq!(line(point2(3, -1), point2(3, -1)).count(), 1);
+        assert_eq!(
+            line(point2(2, 2), point2(-1, -1)).last(),
+            Some(point2(-1, -1))
+        );
+
+        assert_eq!(
+            lattice_points_on_line(point2(0, 0), point2(6, -4)).collect_vec(),
+            vec![point2(0, 0), point2(3, -2), point2(6, -4)]
+        );
+        assert_eq!(primitive_heading(vec2(0, -5)), (vec2(0, -1), 5));
+
+        let grid = Grid::new_with_lines(["abc", "def"].iter());
+        assert_eq!(
+            ray(point2(0, 0), vec2(1, 1))
+                .take_while_in(&grid)
+                .map(|cell| *cell.contents())
+                .collect::<String>(),
+            "ae"
+        );
+        assert_eq!(ray(point2(1, 1), vec2(0, -1)).nth(3), Some(point2(1, -2)));
+    }
+
+    #[test]
+    fn test_visible_from() {
+        let grid = Grid::new_with_lines([".....", ".#...", ".....", "....."].iter());
+        let visible = grid.visible_from(point2(2, 2), |&c| c == '#');
+        let text = grid
+            .render(|cell| {
+                if visible.contains(cell.location()) {
+                    *cell.contents()
+                } else {
+                    '?'
+                }
+            })
+            .to_string();
+        assert_eq!(text, "?....\n.#...\n.....\n.....\n");
+    }
+}
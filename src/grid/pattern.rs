@@ -0,0 +1,219 @@
+use crate::prelude::*;
+
+/// One of the eight rotations and reflections of a square: an optional left-right mirror,
+/// followed by some number of clockwise quarter turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transform {
+    pub reflect: bool,
+    pub quarter_turns: u8,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        reflect: false,
+        quarter_turns: 0,
+    };
+
+    pub const ROTATIONS: [Transform; 4] = [
+        Transform::IDENTITY,
+        Transform {
+            reflect: false,
+            quarter_turns: 1,
+        },
+        Transform {
+            reflect: false,
+            quarter_turns: 2,
+        },
+        Transform {
+            reflect: false,
+            quarter_turns: 3,
+        },
+    ];
+
+    pub const ALL: [Transform; 8] = [
+        Transform::ROTATIONS[0],
+        Transform::ROTATIONS[1],
+        Transform::ROTATIONS[2],
+        Transform::ROTATIONS[3],
+        Transform {
+            reflect: true,
+            quarter_turns: 0,
+        },
+        Transform {
+            reflect: true,
+            quarter_turns: 1,
+        },
+        Transform {
+            reflect: true,
+            quarter_turns: 2,
+        },
+        Transform {
+            reflect: true,
+            quarter_turns: 3,
+        },
+    ];
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        let height = self.height as Index;
+        Grid::new((), self.height, self.width)
+            .map(|c| self[Location::new(c.location().y, height - 1 - c.location().x)].clone())
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn reflect(&self) -> Grid<T> {
+        let width = self.width as Index;
+        self.map(|c| self[Location::new(width - 1 - c.location().x, c.location().y)].clone())
+    }
+
+    pub fn transform(&self, transform: Transform) -> Grid<T> {
+        let mut result = if transform.reflect {
+            self.reflect()
+        } else {
+            self.clone()
+        };
+        for _ in 0..transform.quarter_turns {
+            result = result.rotate_clockwise();
+        }
+        result
+    }
+}
+
+/// A small grid to search for, where None matches anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<T> {
+    grid: Grid<Option<T>>,
+}
+
+impl<T> Pattern<T> {
+    pub fn new(grid: Grid<Option<T>>) -> Self {
+        Self { grid }
+    }
+}
+
+impl Pattern<char> {
+    /// Parses a pattern from lines of text, where `wildcard` matches anything.
+    pub fn new_with_lines(lines: impl Iterator<Item = impl AsRef<str>>, wildcard: char) -> Self {
+        Self::new(
+            Grid::new_with_lines(lines).map(|c| Some(*c.contents()).filter(|&c| c != wildcard)),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// Location of the top-left corner of the (transformed) pattern.
+    pub location: Location,
+    pub transform: Transform,
+}
+
+impl<T: PartialEq + Clone> Grid<T> {
+    /// Every place the pattern matches under any of the given transforms. Transforms which
+    /// leave the pattern unchanged (because it is symmetric) are only reported once.
+    pub fn find_pattern(
+        &self,
+        pattern: &Pattern<T>,
+        transforms: &[Transform],
+    ) -> Vec<PatternMatch> {
+        let mut variants: Vec<(Transform, Grid<Option<T>>)> = vec![];
+        for &transform in transforms {
+            let variant = pattern.grid.transform(transform);
+            if !variants.iter().any(|(_, v)| *v == variant) {
+                variants.push((transform, variant));
+            }
+        }
+
+        let mut matches = vec![];
+        for (transform, variant) in variants {
+            if variant.width > self.width || variant.height > self.height {
+                continue;
+            }
+            let candidates = Grid::new(
+                (),
+                self.width - variant.width + 1,
+                self.height - variant.height + 1,
+            );
+            for location in candidates.locations() {
+                let found = variant.cells().all(|c| {
+                    c.contents().as_ref().is_none_or(|expected| {
+                        *expected == self[location + c.location().to_vector()]
+                    })
+                });
+                if found {
+                    matches.push(PatternMatch {
+                        location,
+                        transform,
+                    });
+                }
+            }
+        }
+        matches
+    }
+}
+
+impl Grid<char> {
+    /// Every (start, heading) from which `word` can be read in a straight line.
+    pub fn find_word(
+        &self,
+        word: &str,
+        headings: impl IntoIterator<Item = Heading>,
+    ) -> Vec<(Location, Heading)> {
+        let headings = headings.into_iter().collect_vec();
+        self.cells()
+            .flat_map(|cell| headings.iter().map(move |&heading| (cell, heading)))
+            .filter(|(cell, heading)| {
+                cell.walk_inclusive(heading.x, heading.y)
+                    .map(|c| *c.contents())
+                    .take(word.chars().count())
+                    .eq(word.chars())
+            })
+            .map(|(cell, heading)| (cell.location(), heading))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_transforms() {
+        let grid = Grid::new_with_lines(["ab", "cd", "ef"].iter());
+        assert_eq!(grid.rotate_clockwise().to_string(), "eca\nfdb\n");
+        assert_eq!(grid.reflect().to_string(), "ba\ndc\nfe\n");
+        let distinct = Transform::ALL
+            .iter()
+            .map(|&t| grid.transform(t).to_string())
+            .unique()
+            .count();
+        assert_eq!(distinct, 8);
+    }
+
+    #[test]
+    fn test_find_word() {
+        let grid = Grid::new_with_lines(["XMAS", "MMXA", "AXAS", "SAMX"].iter());
+        assert_eq!(
+            grid.find_word("XMAS", all_headings()),
+            vec![
+                (Location::new(0, 0), EAST),
+                (Location::new(0, 0), SOUTH),
+                (Location::new(3, 3), WEST)
+            ]
+        );
+        assert!(grid.find_word("XMAS", [NORTH]).is_empty());
+    }
+
+    #[test]
+    fn test_find_pattern() {
+        let grid = Grid::new_with_lines(["MXSXM", "XAXAX", "MXSXM"].iter());
+        let pattern = Pattern::new_with_lines(["M.S", ".A.", "M.S"].iter(), '.');
+        let matches = grid.find_pattern(&pattern, &Transform::ALL);
+        assert_eq!(
+            matches.iter().map(|m| m.location).collect_vec(),
+            vec![Location::new(0, 0), Location::new(2, 0)]
+        );
+        assert_eq!(matches[0].transform, Transform::IDENTITY);
+        assert_eq!(matches[1].transform, Transform::ROTATIONS[2]);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub step: usize,
+    pub location: Location,
+    pub old: T,
+    pub new: T,
+}
+
+/// A grid which records every `set`, grouped into numbered steps, so the changes can be
+/// inspected afterwards and the grid replayed forward and backward.
+#[derive(Debug, Clone)]
+pub struct Recording<T> {
+    grid: Grid<T>,
+    changes: Vec<Change<T>>,
+    // the step that new changes are recorded into
+    step: usize,
+    // how many of `changes` are currently applied to `grid`
+    applied: usize,
+}
+
+impl<T: Clone> Recording<T> {
+    pub fn new(grid: Grid<T>) -> Self {
+        Self {
+            grid,
+            changes: vec![],
+            step: 0,
+            applied: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    pub fn into_grid(mut self) -> Grid<T> {
+        self.fast_forward();
+        self.grid
+    }
+
+    /// The step which `set` currently records into.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn set(&mut self, location: Location, value: T) {
+        assert_eq!(
+            self.applied,
+            self.changes.len(),
+            "Can only record changes after fast-forwarding to the end"
+        );
+        let location = self
+            .grid
+            .normalize(location)
+            .expect("Location should be in bounds");
+        let old = std::mem::replace(&mut self.grid[location], value.clone());
+        self.changes.push(Change {
+            step: self.step,
+            location,
+            old,
+            new: value,
+        });
+        self.applied += 1;
+    }
+
+    /// Finishes the current step; later changes are recorded as part of the next one.
+    pub fn next_step(&mut self) {
+        self.step += 1;
+    }
+
+    /// Changes made during the given step, in the order they were made.
+    pub fn changes_in(&self, step: usize) -> &[Change<T>] {
+        let start = self.changes.partition_point(|c| c.step < step);
+        let end = self.changes.partition_point(|c| c.step <= step);
+        &self.changes[start..end]
+    }
+
+    /// The smallest box containing every change made during the given step.
+    pub fn changed_box(&self, step: usize) -> Option<Box2D<Index>> {
+        let (min, max) =
+            self.changes_in(step)
+                .iter()
+                .map(|c| c.location)
+                .fold(None, |acc, l| match acc {
+                    None => Some((l, l)),
+                    Some((min, max)) => Some((min.min(l), max.max(l))),
+                })?;
+        Some(Box2D::new(min, max + vec2(1, 1)))
+    }
+
+    /// The part of the grid (in its current state) changed during the given step.
+    pub fn changed_region(&self, step: usize) -> Option<(Box2D<Index>, Grid<T>)> {
+        let bounds = self.changed_box(step)?;
+        Some((bounds, self.grid.crop(bounds)))
+    }
+
+    /// Replays or reverts changes so the grid is as it was at the start of the given step.
+    pub fn seek(&mut self, step: usize) {
+        let target = self.changes.partition_point(|c| c.step < step);
+        while self.applied > target {
+            self.applied -= 1;
+            let change = &self.changes[self.applied];
+            self.grid[change.location] = change.old.clone();
+        }
+        while self.applied < target {
+            let change = &self.changes[self.applied];
+            self.grid[change.location] = change.new.clone();
+            self.applied += 1;
+        }
+    }
+
+    pub fn rewind(&mut self) {
+        self.seek(0);
+    }
+
+    pub fn fast_forward(&mut self) {
+        self.seek(usize::MAX);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_recording_replay() {
+        let start = Grid::new_with_lines(["...", "..."].iter());
+        let mut recording = Recording::new(start.clone());
+        recording.set(Location::new(0, 0), 'a');
+        recording.next_step();
+        recording.next_step();
+        recording.set(Location::new(2, 1), 'b');
+        recording.set(Location::new(0, 0), 'c');
+        let end = recording.grid().clone();
+
+        assert_eq!(recording.changes_in(1), &[]);
+        assert_eq!(recording.changes_in(2).len(), 2);
+        let (bounds, region) = recording.changed_region(2).unwrap();
+        assert_eq!(bounds, Box2D::new(point2(0, 0), point2(3, 2)));
+        assert_eq!(region.to_string(), "c..\n..b\n");
+
+        recording.seek(1);
+        assert_eq!(recording.grid().to_string(), "a..\n...\n");
+        recording.rewind();
+        assert!(recording.grid().diff(&start).is_empty());
+        recording.fast_forward();
+        assert_eq!(
+            start.diff(recording.grid()),
+            vec![
+                (Location::new(0, 0), '.', 'c'),
+                (Location::new(2, 1), '.', 'b')
+            ]
+        );
+        assert!(recording.into_grid().diff(&end).is_empty());
+    }
+}
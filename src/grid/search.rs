@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use itertools::Either;
+
+use crate::prelude::*;
+
+impl<T, Tp: Topology> Grid<T, Tp> {
+    /// Breadth-first distances from the nearest of `starts`, moving cardinally through
+    /// passable cells. Unreachable cells are None. The starts themselves are always
+    /// distance 0, whether or not they are passable.
+    pub fn bfs_distances(
+        &self,
+        starts: impl IntoIterator<Item = Location>,
+        mut passable: impl FnMut(&T) -> bool,
+    ) -> Grid<Option<u32>, Tp> {
+        let mut distances = Grid::new(None, self.width, self.height).with_topology();
+        let mut queue = VecDeque::new();
+        for start in starts {
+            let start = self.normalize(start).expect("Start should be on the grid");
+            if distances[start].is_none() {
+                distances[start] = Some(0);
+                queue.push_back(start);
+            }
+        }
+
+        while let Some(location) = queue.pop_front() {
+            let distance = distances[location].unwrap();
+            for next in self.neighbors(location, Connectivity::Four) {
+                if distances[next].is_none() && passable(&self[next]) {
+                    distances[next] = Some(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Every location on the grid within `max_manhattan` of `start` (including `start` itself),
+    /// with its manhattan distance. This ignores the contents of the grid, so it's useful for
+    /// "teleport up to N steps" searches. On a toroidal grid the distance is the shortest one
+    /// around the wrap, and each location appears once.
+    pub fn distances_within(
+        &self,
+        start: Location,
+        max_manhattan: usize,
+    ) -> impl Iterator<Item = (Location, usize)> + '_ {
+        let max = max_manhattan as Index;
+        let diamond = (-max..=max).flat_map(move |dy| {
+            let remaining = max - dy.abs();
+            (-remaining..=remaining).filter_map(move |dx| {
+                let location = self.normalize(start + vec2(dx, dy))?;
+                Some((location, (dx.abs() + dy.abs()) as usize))
+            })
+        });
+        if 2 * max_manhattan < self.width().min(self.height()) {
+            // no two offsets in the diamond can wrap onto the same location
+            Either::Left(diamond)
+        } else {
+            let mut nearest = LocationMap::for_grid(self);
+            for (location, distance) in diamond {
+                if nearest.get(location).is_none_or(|&d| distance < d) {
+                    nearest.insert(location, distance);
+                }
+            }
+            let nearest = nearest.iter().map(|(l, &d)| (l, d)).collect_vec();
+            Either::Right(nearest.into_iter())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bfs_matches_dijkstra() {
+        let grid = Grid::new_with_lines(["..#....", ".##.##.", "...#...", "#.....#"].iter());
+        let start = Location::new(0, 0);
+        let distances = grid.bfs_distances([start], |&c| c != '#');
+        let expected = graph::min_distances(start, |&l| {
+            grid.passable_neighbors(l, |&c| c != '#').map(|n| (n, 1))
+        });
+        for location in grid.locations() {
+            assert_eq!(
+                distances[location].map(u64::from),
+                expected.get(&location).copied()
+            );
+        }
+
+        let both_corners = grid.bfs_distances([start, Location::new(6, 0)], |&c| c != '#');
+        assert_eq!(both_corners[Location::new(5, 2)], Some(3));
+    }
+
+    #[test]
+    fn test_distances_within() {
+        let grid = Grid::new('.', 5, 5);
+        assert_eq!(grid.distances_within(Location::new(2, 2), 2).count(), 13);
+        let corner = grid.distances_within(Location::new(0, 0), 2).collect_vec();
+        assert_eq!(corner.len(), 6);
+        assert!(corner
+            .iter()
+            .all(|&(l, d)| d == manhattan_distance(l, Location::new(0, 0))));
+    }
+
+    #[test]
+    fn test_distances_within_toroidal() {
+        let grid = Grid::new('.', 4, 3).with_topology::<Toroidal>();
+        let start = Location::new(0, 0);
+        let distances = grid.distances_within(start, 3).collect_vec();
+        assert_eq!(distances.len(), 12);
+        assert!(distances.iter().map(|&(l, _)| l).all_unique());
+        let distance_to = |target| distances.iter().find(|&&(l, _)| l == target).unwrap().1;
+        assert_eq!(distance_to(Location::new(3, 0)), 1);
+        assert_eq!(distance_to(Location::new(2, 2)), 3);
+        assert_eq!(distance_to(Location::new(0, 2)), 1);
+    }
+}
@@ -0,0 +1,441 @@
+use bitvec::vec::BitVec;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    width: usize,
+    height: usize,
+}
+
+impl Bounds {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn index(&self, location: Location) -> Option<usize> {
+        if location.x < 0
+            || location.y < 0
+            || location.x >= self.width as Index
+            || location.y >= self.height as Index
+        {
+            None
+        } else {
+            Some(location.y as usize * self.width + location.x as usize)
+        }
+    }
+
+    fn location(&self, index: usize) -> Location {
+        Location::new((index % self.width) as Index, (index / self.width) as Index)
+    }
+}
+
+/// A set of locations on a grid of a fixed size, stored as a bitset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationSet {
+    bounds: Bounds,
+    bits: BitVec,
+}
+
+impl LocationSet {
+    pub fn new(width: usize, height: usize) -> Self {
+        let bounds = Bounds { width, height };
+        Self {
+            bounds,
+            bits: BitVec::repeat(false, bounds.len()),
+        }
+    }
+
+    pub fn for_grid<T, Tp: Topology>(grid: &Grid<T, Tp>) -> Self {
+        Self::new(grid.width(), grid.height())
+    }
+
+    pub fn width(&self) -> usize {
+        self.bounds.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.bounds.height
+    }
+
+    /// Returns true if the location was not already in the set.
+    pub fn insert(&mut self, location: Location) -> bool {
+        let index = self
+            .bounds
+            .index(location)
+            .expect("Location should be in bounds");
+        !self.bits.replace(index, true)
+    }
+
+    /// Returns true if the location was in the set.
+    pub fn remove(&mut self, location: Location) -> bool {
+        match self.bounds.index(location) {
+            Some(index) => self.bits.replace(index, false),
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, location: Location) -> bool {
+        self.bounds
+            .index(location)
+            .is_some_and(|index| self.bits[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Location> + '_ {
+        self.bits
+            .iter_ones()
+            .map(|index| self.bounds.location(index))
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.not_any()
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(false);
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        assert_eq!(self.bounds, other.bounds);
+        *self.bits |= &*other.bits;
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.union_with(other);
+        result
+    }
+}
+
+impl Extend<Location> for LocationSet {
+    fn extend<I: IntoIterator<Item = Location>>(&mut self, iter: I) {
+        for location in iter {
+            self.insert(location);
+        }
+    }
+}
+
+impl graph::VisitedSet<Location> for LocationSet {
+    fn insert(&mut self, node: Location) -> bool {
+        LocationSet::insert(self, node)
+    }
+
+    fn contains(&self, node: &Location) -> bool {
+        LocationSet::contains(self, *node)
+    }
+}
+
+impl<'a, T, Tp: Topology> graph::VisitedSet<Cell<'a, T, Tp>> for LocationSet {
+    fn insert(&mut self, node: Cell<'a, T, Tp>) -> bool {
+        LocationSet::insert(self, node.location())
+    }
+
+    fn contains(&self, node: &Cell<'a, T, Tp>) -> bool {
+        LocationSet::contains(self, node.location())
+    }
+}
+
+/// A map from locations on a grid of a fixed size to values, stored densely.
+#[derive(Debug, Clone)]
+pub struct LocationMap<T> {
+    bounds: Bounds,
+    values: Vec<Option<T>>,
+    len: usize,
+}
+
+impl<T> LocationMap<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        let bounds = Bounds { width, height };
+        Self {
+            bounds,
+            values: std::iter::repeat_with(|| None).take(bounds.len()).collect(),
+            len: 0,
+        }
+    }
+
+    pub fn for_grid<U, Tp: Topology>(grid: &Grid<U, Tp>) -> Self {
+        Self::new(grid.width(), grid.height())
+    }
+
+    /// Returns the previous value at this location, if any.
+    pub fn insert(&mut self, location: Location, value: T) -> Option<T> {
+        let index = self
+            .bounds
+            .index(location)
+            .expect("Location should be in bounds");
+        let previous = self.values[index].replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    pub fn remove(&mut self, location: Location) -> Option<T> {
+        let previous = self.values[self.bounds.index(location)?].take();
+        if previous.is_some() {
+            self.len -= 1;
+        }
+        previous
+    }
+
+    pub fn get(&self, location: Location) -> Option<&T> {
+        self.values[self.bounds.index(location)?].as_ref()
+    }
+
+    pub fn get_mut(&mut self, location: Location) -> Option<&mut T> {
+        self.values[self.bounds.index(location)?].as_mut()
+    }
+
+    pub fn contains_key(&self, location: Location) -> bool {
+        self.get(location).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Location, &T)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| Some((self.bounds.location(index), value.as_ref()?)))
+    }
+
+    pub fn keys(&self) -> LocationSet {
+        let mut result = LocationSet::new(self.bounds.width, self.bounds.height);
+        result.extend(self.iter().map(|(location, _)| location));
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// A small state which can be stored alongside a location in a `StateSet`, such as a heading.
+pub trait SmallState: Copy {
+    /// The number of distinct states; `index` must be less than this.
+    const COUNT: usize;
+
+    fn index(self) -> usize;
+
+    fn from_index(index: usize) -> Self;
+}
+
+impl SmallState for Direction {
+    const COUNT: usize = 8;
+
+    fn index(self) -> usize {
+        Direction::index(self)
+    }
+
+    fn from_index(index: usize) -> Self {
+        Direction::from_index(index)
+    }
+}
+
+/// A direction which is always cardinal, so a `StateSet<Cardinal>` takes half the bits of a
+/// `StateSet<Direction>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cardinal(pub Direction);
+
+impl SmallState for Cardinal {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        self.0.index()
+    }
+
+    fn from_index(index: usize) -> Self {
+        Cardinal(Direction::from_index(index))
+    }
+}
+
+impl SmallState for bool {
+    const COUNT: usize = 2;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn from_index(index: usize) -> Self {
+        index != 0
+    }
+}
+
+/// A set of (location, state) pairs on a grid of a fixed size, stored as a bitset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSet<S> {
+    bounds: Bounds,
+    bits: BitVec,
+    _state: std::marker::PhantomData<S>,
+}
+
+impl<S: SmallState> StateSet<S> {
+    pub fn new(width: usize, height: usize) -> Self {
+        let bounds = Bounds { width, height };
+        Self {
+            bounds,
+            bits: BitVec::repeat(false, bounds.len() * S::COUNT),
+            _state: std::marker::PhantomData,
+        }
+    }
+
+    pub fn for_grid<T, Tp: Topology>(grid: &Grid<T, Tp>) -> Self {
+        Self::new(grid.width(), grid.height())
+    }
+
+    fn index(&self, location: Location, state: S) -> Option<usize> {
+        let state_index = state.index();
+        debug_assert!(state_index < S::COUNT);
+        Some(self.bounds.index(location)? * S::COUNT + state_index)
+    }
+
+    /// Returns true if the pair was not already in the set.
+    ///
+    /// The location must be in bounds. This sits in the inner loop of most walks, so that is only
+    /// checked in debug builds.
+    pub fn insert(&mut self, location: Location, state: S) -> bool {
+        debug_assert!(
+            self.bounds.index(location).is_some(),
+            "Location should be in bounds"
+        );
+        let state_index = state.index();
+        debug_assert!(state_index < S::COUNT);
+        let cell = location.y as usize * self.bounds.width + location.x as usize;
+        !self.bits.replace(cell * S::COUNT + state_index, true)
+    }
+
+    pub fn contains(&self, location: Location, state: S) -> bool {
+        self.index(location, state)
+            .is_some_and(|index| self.bits[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Location, S)> + '_ {
+        self.bits.iter_ones().map(|index| {
+            (
+                self.bounds.location(index / S::COUNT),
+                S::from_index(index % S::COUNT),
+            )
+        })
+    }
+
+    /// All locations which are in the set with any state.
+    pub fn locations(&self) -> LocationSet {
+        let mut result = LocationSet::new(self.bounds.width, self.bounds.height);
+        result.extend(self.iter().map(|(location, _)| location));
+        result
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.not_any()
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        assert_eq!(self.bounds, other.bounds);
+        *self.bits |= &*other.bits;
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.union_with(other);
+        result
+    }
+}
+
+impl<S: SmallState> graph::VisitedSet<(Location, S)> for StateSet<S> {
+    fn insert(&mut self, (location, state): (Location, S)) -> bool {
+        StateSet::insert(self, location, state)
+    }
+
+    fn contains(&self, (location, state): &(Location, S)) -> bool {
+        StateSet::contains(self, *location, *state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_location_set() {
+        let mut a = LocationSet::new(3, 2);
+        assert!(a.insert(Location::new(2, 1)));
+        assert!(!a.insert(Location::new(2, 1)));
+        assert!(!a.contains(Location::new(3, 1)));
+
+        let mut b = LocationSet::new(3, 2);
+        b.extend([Location::new(0, 0), Location::new(2, 1)]);
+        let union = a.union(&b);
+        assert_eq!(union.count(), 2);
+        assert_eq!(
+            union.iter().collect_vec(),
+            vec![Location::new(0, 0), Location::new(2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_location_map() {
+        let mut map = LocationMap::new(3, 2);
+        assert!(map.is_empty());
+        assert_eq!(map.insert(Location::new(2, 1), 'a'), None);
+        assert_eq!(map.insert(Location::new(0, 0), 'b'), None);
+        assert_eq!(map.insert(Location::new(2, 1), 'c'), Some('a'));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(Location::new(2, 1)), Some(&'c'));
+        assert_eq!(map.get(Location::new(3, 1)), None);
+        assert_eq!(map.get(Location::new(-1, 0)), None);
+        assert_eq!(
+            map.keys().iter().collect_vec(),
+            vec![Location::new(0, 0), Location::new(2, 1)]
+        );
+
+        assert_eq!(map.remove(Location::new(0, 0)), Some('b'));
+        assert_eq!(map.remove(Location::new(0, 0)), None);
+        assert_eq!(map.len(), 1);
+        assert!(!map.contains_key(Location::new(0, 0)));
+    }
+
+    #[test]
+    fn test_state_set() {
+        let mut set = StateSet::new(2, 2);
+        assert!(set.insert(Location::new(1, 0), Direction::West));
+        assert!(set.insert(Location::new(1, 0), Direction::North));
+        assert!(!set.insert(Location::new(1, 0), Direction::West));
+        assert!(set.contains(Location::new(1, 0), Direction::North));
+        assert!(!set.contains(Location::new(1, 0), Direction::South));
+        assert_eq!(set.count(), 2);
+        assert_eq!(set.locations().count(), 1);
+        assert_eq!(
+            set.iter().collect_vec(),
+            vec![
+                (Location::new(1, 0), Direction::North),
+                (Location::new(1, 0), Direction::West)
+            ]
+        );
+
+        let mut cardinal = StateSet::new(2, 2);
+        assert!(cardinal.insert(Location::new(1, 1), Cardinal(Direction::West)));
+        assert!(cardinal.contains(Location::new(1, 1), Cardinal(Direction::West)));
+        assert_eq!(cardinal.bits.len(), 2 * 2 * 4);
+        assert_eq!(
+            cardinal.iter().collect_vec(),
+            vec![(Location::new(1, 1), Cardinal(Direction::West))]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cardinal_state_rejects_diagonals() {
+        let mut set = StateSet::new(2, 2);
+        set.insert(Location::new(0, 0), Cardinal(Direction::NorthEast));
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const RED: Rgb = Rgb(255, 0, 0);
+    pub const GREEN: Rgb = Rgb(0, 255, 0);
+    pub const BLUE: Rgb = Rgb(0, 0, 255);
+}
+
+impl<T> Grid<T> {
+    /// Row-major RGB bytes, one pixel per cell.
+    fn rgb_bytes(&self, mut color: impl FnMut(Cell<T>) -> Rgb) -> Vec<u8> {
+        self.cells()
+            .flat_map(|cell| {
+                let Rgb(r, g, b) = color(cell);
+                [r, g, b]
+            })
+            .collect()
+    }
+
+    /// Writes a binary PPM image with one pixel per cell.
+    pub fn write_ppm(
+        &self,
+        path: impl AsRef<Path>,
+        color: impl FnMut(Cell<T>) -> Rgb,
+    ) -> anyhow::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&encode_ppm(
+            self.width(),
+            self.height(),
+            &self.rgb_bytes(color),
+        ))?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Writes a PNG image with one pixel per cell.
+    pub fn write_png(
+        &self,
+        path: impl AsRef<Path>,
+        color: impl FnMut(Cell<T>) -> Rgb,
+    ) -> anyhow::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width() as u32, self.height() as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgb_bytes(color))?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn encode_ppm(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut result = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    result.extend_from_slice(rgb);
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameFormat {
+    Ppm,
+    Png,
+}
+
+enum FrameOutput {
+    Numbered {
+        directory: PathBuf,
+        format: FrameFormat,
+    },
+    Gif(gif::Encoder<BufWriter<File>>),
+}
+
+/// Records a sequence of grids (e.g. each step of a simulation), either as numbered
+/// image files in a directory or as frames of an animated GIF.
+pub struct FrameRecorder {
+    output: FrameOutput,
+    frame_count: usize,
+    // in hundredths of a second; only used for GIFs
+    delay: u16,
+}
+
+impl FrameRecorder {
+    /// Writes each frame to `directory/00000.png` (or `.ppm`), `directory/00001.png`, etc.
+    pub fn numbered(directory: impl Into<PathBuf>, format: FrameFormat) -> anyhow::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self {
+            output: FrameOutput::Numbered { directory, format },
+            frame_count: 0,
+            delay: 0,
+        })
+    }
+
+    /// Writes every frame into a single looping animated GIF. All frames must be the given size.
+    pub fn gif(
+        path: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+        delay: u16,
+    ) -> anyhow::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = gif::Encoder::new(file, width.try_into()?, height.try_into()?, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(Self {
+            output: FrameOutput::Gif(encoder),
+            frame_count: 0,
+            delay,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn record<T>(
+        &mut self,
+        grid: &Grid<T>,
+        color: impl FnMut(Cell<T>) -> Rgb,
+    ) -> anyhow::Result<()> {
+        match &mut self.output {
+            FrameOutput::Numbered { directory, format } => {
+                let path = directory.join(format!("{:05}", self.frame_count));
+                match format {
+                    FrameFormat::Ppm => grid.write_ppm(path.with_extension("ppm"), color)?,
+                    FrameFormat::Png => grid.write_png(path.with_extension("png"), color)?,
+                }
+            }
+            FrameOutput::Gif(encoder) => {
+                let mut frame = gif::Frame::from_rgb_speed(
+                    grid.width().try_into()?,
+                    grid.height().try_into()?,
+                    &grid.rgb_bytes(color),
+                    10,
+                );
+                frame.delay = self.delay;
+                encoder.write_frame(&frame)?;
+            }
+        }
+        self.frame_count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ppm_encoding() {
+        let grid = Grid::new_with_lines(["#.", ".#"].iter());
+        let bytes = grid.rgb_bytes(|c| {
+            if *c.contents() == '#' {
+                Rgb::WHITE
+            } else {
+                Rgb::RED
+            }
+        });
+        assert_eq!(
+            encode_ppm(grid.width(), grid.height(), &bytes),
+            b"P6\n2 2\n255\n\xff\xff\xff\xff\x00\x00\xff\x00\x00\xff\xff\xff"
+        );
+    }
+
+    /// A path in the system temp directory which no other test run will use.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("aoc2024-{}-{}", std::process::id(), name))
+    }
+
+    fn color(cell: Cell<char>) -> Rgb {
+        if *cell.contents() == '#' {
+            Rgb::WHITE
+        } else {
+            Rgb::RED
+        }
+    }
+
+    #[test]
+    fn test_png_round_trip() -> anyhow::Result<()> {
+        let grid = Grid::new_with_lines(["#..", ".#."].iter());
+        let path = temp_path("round_trip.png");
+        grid.write_png(&path, color)?;
+
+        let mut reader = png::Decoder::new(File::open(&path)?).read_info()?;
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(&pixels[..6], &[255, 255, 255, 255, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_recorder() -> anyhow::Result<()> {
+        let grid = Grid::new_with_lines(["#..", ".#."].iter());
+
+        let path = temp_path("frames.gif");
+        let mut recorder = FrameRecorder::gif(&path, 3, 2, 5)?;
+        for _ in 0..3 {
+            recorder.record(&grid, color)?;
+        }
+        assert_eq!(recorder.frame_count(), 3);
+        // the GIF trailer is written when the encoder is dropped
+        drop(recorder);
+
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path)?)?;
+        assert_eq!((decoder.width(), decoder.height()), (3, 2));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame()? {
+            assert_eq!(frame.delay, 5);
+            frames += 1;
+        }
+        std::fs::remove_file(&path)?;
+        assert_eq!(frames, 3);
+
+        let directory = temp_path("frames");
+        let mut recorder = FrameRecorder::numbered(&directory, FrameFormat::Ppm)?;
+        recorder.record(&grid, color)?;
+        recorder.record(&grid, color)?;
+        let first = std::fs::read(directory.join("00000.ppm"))?;
+        let second_exists = directory.join("00001.ppm").exists();
+        std::fs::remove_dir_all(&directory)?;
+        assert!(first.starts_with(b"P6\n3 2\n255\n"));
+        assert!(second_exists);
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use clap::Parser;
 pub mod prelude {
     pub use super::graph;
     pub use super::grid::*;
+    pub use super::image;
     pub use bitvec;
     pub use euclid::{default::*, point2, vec2};
     pub use hashbag::HashBag;
@@ -20,6 +21,7 @@ pub mod prelude {
 
 pub mod graph;
 pub mod grid;
+pub mod image;
 
 // Inspired by https://git.sr.ht/~gadanidis/aoc2024/tree/main/item/src/main.rs
 